@@ -1,60 +1,394 @@
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use color_eyre::Result;
-use itertools::Itertools;
-use once_cell::sync::OnceCell;
-use std::env;
+use std::{
+    io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
 
-use crate::SortingOption;
+use crate::{
+    config::CONFIG_PATH_VAR,
+    export::ExportFormat,
+    report::ReportFormat,
+    shared::{Order, SortingOption, SortingValue},
+};
 
-static INSTANCE: OnceCell<Arguments> = OnceCell::new();
+static INSTANCE: RwLock<Option<Arc<Arguments>>> = RwLock::new(None);
+
+/// Finds media nobody watches anymore and removes it from Overseerr, Radarr and Sonarr.
+///
+/// Without a command it gathers the available requests and lets you choose what to delete.
+#[derive(Debug, Parser)]
+#[command(
+    name = "media-cleaner",
+    version,
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+    #[command(flatten)]
+    items: ItemArgs,
+    /// Ignore the cached responses and fetch everything from the services again.
+    #[arg(long, global = true)]
+    refresh: bool,
+    /// The config file to use, instead of looking for a config.yaml in the working directory,
+    /// the user's config directory and /etc/media-cleaner.
+    #[arg(long, global = true, env = CONFIG_PATH_VAR)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommand {
+    /// Choose what to delete from the available requests. The default command.
+    Clean(ItemArgs),
+    /// Print the available requests, without deleting anything.
+    List(ItemArgs),
+    /// Write the available requests as JSON or CSV, for use in other tools.
+    Export(ExportArgs),
+    /// Write a report of the space taken up by unwatched and duplicated media.
+    Report(ReportArgs),
+    /// Check that the config can be read, without contacting any of the services.
+    ValidateConfig,
+    /// Contact every service to check its URL, API key and version, and that Overseerr uses the
+    /// same Radarr and Sonarr servers.
+    Doctor,
+    /// Remove media on a schedule, following the rules in the config.
+    Daemon,
+    /// Serve the web UI and its API.
+    Serve(AllMediaArg),
+    /// Finish a deletion that was interrupted.
+    Resume,
+    /// Add deleted items back to Radarr and Sonarr.
+    Restore {
+        /// Make the Overseerr request again for the restored items.
+        #[arg(long)]
+        with_request: bool,
+    },
+    /// Print a completion script for the shell.
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page.
+    Man,
+}
+
+#[derive(Debug, Args)]
+struct AllMediaArg {
+    /// Include every available item on the server, not just the requested ones.
+    #[arg(short = 'C', long)]
+    all_media: bool,
+}
+
+#[derive(Debug, Args)]
+struct ItemArgs {
+    #[command(flatten)]
+    sort: SortArgs,
+    #[command(flatten)]
+    all_media: AllMediaArg,
+}
+
+/// The order to show the items in, as `--sort` or one of the flags for each order.
+#[derive(Debug, Args)]
+#[group(multiple = false)]
+struct SortArgs {
+    /// The order to show the items in. Asked for when cleaning without it.
+    #[arg(short, long, value_enum)]
+    sort: Option<Sort>,
+    /// Sort by size, largest first.
+    #[arg(long)]
+    size: bool,
+    /// Sort by size, smallest first.
+    #[arg(long)]
+    size_asc: bool,
+    /// Sort by name, from A to Z.
+    #[arg(long)]
+    name: bool,
+    /// Sort by name, from Z to A.
+    #[arg(long)]
+    name_desc: bool,
+    /// Sort by media type.
+    #[arg(long = "type")]
+    media_type: bool,
+    /// Sort by request date, oldest first.
+    #[arg(long)]
+    requested: bool,
+    /// Sort by request date, newest first.
+    #[arg(long)]
+    requested_desc: bool,
+}
+
+impl SortArgs {
+    fn sorting(&self) -> Option<SortingOption> {
+        let flags = [
+            (self.size, Sort::Size),
+            (self.size_asc, Sort::SizeAsc),
+            (self.name, Sort::Name),
+            (self.name_desc, Sort::NameDesc),
+            (self.media_type, Sort::Type),
+            (self.requested, Sort::Requested),
+            (self.requested_desc, Sort::RequestedDesc),
+        ];
+
+        self.sort
+            .or_else(|| {
+                flags
+                    .into_iter()
+                    .find(|(set, _)| *set)
+                    .map(|(_, sort)| sort)
+            })
+            .map(SortingOption::from)
+    }
+}
+
+#[derive(Debug, Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    items: ItemArgs,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    format: ExportFormat,
+    /// The file to write to, instead of stdout.
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct ReportArgs {
+    #[command(flatten)]
+    all_media: AllMediaArg,
+    /// Defaults to HTML when the output file ends in .html, otherwise markdown.
+    #[arg(long, value_enum)]
+    format: Option<ReportFormat>,
+    /// The file to write to, instead of stdout.
+    #[arg(short, long)]
+    output: Option<String>,
+    /// The number of items in each list.
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+/// The orders the items can be shown in, also taken by the `sort` of `GET /candidates`.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Sort {
+    /// By name, from A to Z.
+    Name,
+    /// By name, from Z to A.
+    NameDesc,
+    /// Largest first.
+    Size,
+    /// Smallest first.
+    SizeAsc,
+    /// By media type.
+    Type,
+    /// Oldest request first.
+    Requested,
+    /// Newest request first.
+    RequestedDesc,
+}
+
+impl From<Sort> for SortingOption {
+    fn from(sort: Sort) -> Self {
+        let (sorting_value, sorting_direction) = match sort {
+            Sort::Name => (SortingValue::Name, Order::Asc),
+            Sort::NameDesc => (SortingValue::Name, Order::Desc),
+            Sort::Size => (SortingValue::Size, Order::Desc),
+            Sort::SizeAsc => (SortingValue::Size, Order::Asc),
+            Sort::Type => (SortingValue::Type, Order::Desc),
+            Sort::Requested => (SortingValue::RequestedDate, Order::Asc),
+            Sort::RequestedDesc => (SortingValue::RequestedDate, Order::Desc),
+        };
+
+        SortingOption {
+            sorting_value,
+            sorting_direction,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Clean,
+    List,
+    Export,
+    Report,
+    ValidateConfig,
+    Doctor,
+    Daemon,
+    Serve,
+    Resume,
+    Restore,
+    Completions(Shell),
+    Man,
+}
+
+impl Command {
+    /// Whether the command needs the config, which is everything but printing documentation.
+    pub fn needs_config(&self) -> bool {
+        !matches!(self, Command::Completions(_) | Command::Man)
+    }
+}
+
+#[derive(Debug)]
+pub struct ReportOptions {
+    pub format: ReportFormat,
+    pub output: Option<String>,
+    pub top: usize,
+}
+
+#[derive(Debug)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub output: Option<String>,
+}
 
 #[derive(Debug)]
 pub struct Arguments {
+    pub command: Command,
     pub sorting: Option<SortingOption>,
     pub all_media: bool,
+    pub refresh: bool,
+    /// The config file given on the command line, otherwise it is looked for.
+    pub config: Option<PathBuf>,
+    /// Make the Overseerr request again when restoring an item.
+    pub with_request: bool,
+    pub report: ReportOptions,
+    pub export: ExportOptions,
+}
+
+impl Default for Arguments {
+    fn default() -> Self {
+        Self {
+            command: Command::Clean,
+            sorting: None,
+            all_media: false,
+            refresh: false,
+            config: None,
+            with_request: false,
+            report: ReportOptions {
+                format: ReportFormat::Markdown,
+                output: None,
+                top: 10,
+            },
+            export: ExportOptions {
+                format: ExportFormat::Json,
+                output: None,
+            },
+        }
+    }
 }
 
 impl Arguments {
-    pub fn get_args() -> &'static Arguments {
-        INSTANCE.get().expect("Arguments have not been initialised")
+    pub fn get_args() -> Arc<Arguments> {
+        INSTANCE
+            .read()
+            .expect("Arguments lock has been poisoned.")
+            .clone()
+            .expect("Arguments have not been initialised")
     }
 
-    pub fn read_args() -> Result<()> {
-        if let Some(_) = INSTANCE.get() {
-            return Ok(());
-        }
+    /// Parses the command line, printing the help or a usage error and exiting when asked for
+    /// or when it is not valid.
+    pub fn read_args() {
+        let args = Self::from_cli(Cli::parse());
 
-        let mut args = env::args().collect_vec();
+        *INSTANCE.write().expect("Arguments lock has been poisoned.") = Some(Arc::new(args));
+    }
 
-        let args = Arguments {
-            sorting: Self::read_sort(&mut args),
-            all_media: Self::read_all_media(&mut args),
+    fn from_cli(cli: Cli) -> Self {
+        let mut args = Arguments {
+            refresh: cli.refresh,
+            config: cli.config,
+            ..Default::default()
         };
 
-        INSTANCE
-            .set(args)
-            .expect("Arguments have already been initialized...");
-        Ok(())
-    }
-
-    fn read_sort(args: &mut Vec<String>) -> Option<SortingOption> {
-        for (i, arg) in args.iter_mut().enumerate() {
-            if let Ok(sort) = SortingOption::from_str(&arg[1..]) {
-                args.swap_remove(i);
-                return Some(sort);
+        let items = match cli.command {
+            None => Some(cli.items),
+            Some(CliCommand::Clean(items)) => Some(items),
+            Some(CliCommand::List(items)) => {
+                args.command = Command::List;
+                Some(items)
+            }
+            Some(CliCommand::Export(export)) => {
+                args.command = Command::Export;
+                args.export = ExportOptions {
+                    format: export.format,
+                    output: export.output,
+                };
+                Some(export.items)
             }
+            Some(CliCommand::Report(report)) => {
+                args.command = Command::Report;
+                args.all_media = report.all_media.all_media;
+                let format = report.format.unwrap_or(match report.output {
+                    Some(ref output) if output.ends_with(".html") => ReportFormat::Html,
+                    _ => ReportFormat::Markdown,
+                });
+                args.report = ReportOptions {
+                    format,
+                    output: report.output,
+                    top: report.top,
+                };
+                None
+            }
+            Some(CliCommand::ValidateConfig) => {
+                args.command = Command::ValidateConfig;
+                None
+            }
+            Some(CliCommand::Doctor) => {
+                args.command = Command::Doctor;
+                None
+            }
+            Some(CliCommand::Daemon) => {
+                args.command = Command::Daemon;
+                None
+            }
+            Some(CliCommand::Serve(all_media)) => {
+                args.command = Command::Serve;
+                args.all_media = all_media.all_media;
+                None
+            }
+            Some(CliCommand::Resume) => {
+                args.command = Command::Resume;
+                None
+            }
+            Some(CliCommand::Restore { with_request }) => {
+                args.command = Command::Restore;
+                args.with_request = with_request;
+                None
+            }
+            Some(CliCommand::Completions { shell }) => {
+                args.command = Command::Completions(shell);
+                None
+            }
+            Some(CliCommand::Man) => {
+                args.command = Command::Man;
+                None
+            }
+        };
+
+        if let Some(items) = items {
+            args.sorting = items.sort.sorting();
+            args.all_media = items.all_media.all_media;
         }
 
-        None
+        args
     }
+}
 
-    fn read_all_media(args: &mut Vec<String>) -> bool {
-        for (i, arg) in args.iter_mut().enumerate() {
-            if arg == "-C" {
-                args.swap_remove(i);
-                return true;
-            }
-        }
+/// Writes the completion script for the shell to stdout.
+pub fn print_completions(shell: Shell) {
+    clap_complete::generate(
+        shell,
+        &mut Cli::command(),
+        "media-cleaner",
+        &mut io::stdout(),
+    );
+}
 
-        return false;
-    }
+/// Writes the man page to stdout, in roff.
+pub fn print_man_page() -> Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(&mut io::stdout())?;
+    Ok(())
 }
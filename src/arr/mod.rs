@@ -1,70 +1,145 @@
 mod radarr;
 mod sonarr;
 
-use std::fmt::Display;
+use std::{collections::HashMap, fmt::Display};
 
 use chrono::{DateTime, Utc};
 use color_eyre::owo_colors::OwoColorize;
 use color_eyre::Result;
+use serde::{Deserialize, Serialize};
 
 pub use self::radarr::MovieStatus;
 pub use self::sonarr::SeriesStatus;
 use crate::config::Config;
 use crate::shared::MediaType;
 
-pub fn movie_manger_active() -> bool {
-    match Config::global().radarr {
-        Some(_) => true,
-        None => false,
+/// The name of the instance, matching its section in the config.
+pub fn instance_name(media_type: MediaType, is_4k: bool) -> &'static str {
+    match (media_type, is_4k) {
+        (MediaType::Movie, false) => "radarr",
+        (MediaType::Movie, true) => "radarr_4k",
+        (MediaType::Tv, false) => "sonarr",
+        (MediaType::Tv, true) => "sonarr_4k",
     }
 }
 
+pub fn movie_manger_active() -> bool {
+    Config::global().radarr.is_some()
+}
+
 pub fn movie_4k_manager_active() -> bool {
-    match Config::global().radarr_4k {
-        Some(_) => true,
-        None => false,
-    }
+    Config::global().radarr_4k.is_some()
 }
 
 pub fn tv_manager_active() -> bool {
-    match Config::global().sonarr {
-        Some(_) => true,
-        None => false,
-    }
+    Config::global().sonarr.is_some()
 }
 
 pub fn tv_4k_manager_active() -> bool {
-    match Config::global().sonarr_4k {
-        Some(_) => true,
-        None => false,
-    }
+    Config::global().sonarr_4k.is_some()
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
 pub enum ArrData {
     Movie(MovieData),
     Tv(TvData),
 }
 
-impl ArrData {
-    pub async fn get_data(media_type: MediaType, id: i32) -> Result<Self> {
-        match media_type {
-            MediaType::Movie => Ok(Self::Movie(MovieData::get_data(id, false).await?)),
-            MediaType::Tv => Ok(Self::Tv(TvData::get_data(id, false).await?)),
-        }
+/// What is needed to add an item back to its instance after it has been removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArrRecord {
+    pub media_type: MediaType,
+    pub is_4k: bool,
+    pub title: String,
+    pub tmdb_id: Option<i32>,
+    pub tvdb_id: Option<i32>,
+    pub quality_profile_id: i32,
+    /// Only used by Sonarr v3, later versions have dropped language profiles.
+    pub language_profile_id: Option<i32>,
+    pub root_folder_path: Option<String>,
+    pub monitored: bool,
+}
+
+impl ArrRecord {
+    pub fn instance_name(&self) -> &'static str {
+        instance_name(self.media_type, self.is_4k)
     }
+}
 
-    pub async fn get_4k_data(media_type: MediaType, id: i32) -> Result<Self> {
-        match media_type {
-            MediaType::Movie => Ok(Self::Movie(MovieData::get_data(id, true).await?)),
-            MediaType::Tv => Ok(Self::Tv(TvData::get_data(id, true).await?)),
+/// Removes the item and its files from the standard or 4K instance for its media type.
+pub async fn remove_data(record: &ArrRecord, id: i32) -> Result<()> {
+    match record.media_type {
+        MediaType::Movie => radarr::delete_radarr_data_and_files(id, record.is_4k).await,
+        MediaType::Tv => sonarr::remove_sonarr_data_and_files(id, record.is_4k).await,
+    }
+}
+
+/// Adds a removed item back to the instance it was removed from, and searches for it.
+pub async fn restore_data(record: &ArrRecord) -> Result<()> {
+    match record.media_type {
+        MediaType::Movie => radarr::add_movie(record).await,
+        MediaType::Tv => sonarr::add_series(record).await,
+    }
+}
+
+/// The response of `/system/status`, which Radarr and Sonarr share.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemStatus {
+    pub version: String,
+}
+
+/// The version of the standard or 4K instance for the media type, which also checks its API key.
+pub async fn get_version(media_type: MediaType, is_4k: bool) -> Result<String> {
+    let status = match media_type {
+        MediaType::Movie => radarr::get_system_status(is_4k).await?,
+        MediaType::Tv => sonarr::get_system_status(is_4k).await?,
+    };
+
+    Ok(status.version)
+}
+
+impl ArrData {
+    /// Gets everything in the instance at once, by its *arr id.
+    /// Returns nothing if the instance is not configured.
+    pub async fn get_all(media_type: MediaType, is_4k: bool) -> Result<HashMap<i32, Self>> {
+        let active = match (media_type, is_4k) {
+            (MediaType::Movie, false) => movie_manger_active(),
+            (MediaType::Movie, true) => movie_4k_manager_active(),
+            (MediaType::Tv, false) => tv_manager_active(),
+            (MediaType::Tv, true) => tv_4k_manager_active(),
+        };
+        if !active {
+            return Ok(HashMap::new());
         }
+
+        let data = match media_type {
+            MediaType::Movie => radarr::get_all_radarr_data(is_4k)
+                .await?
+                .into_iter()
+                .map(|movie| {
+                    Ok((
+                        movie.id,
+                        Self::Movie(MovieData::from_resource(movie, is_4k)?),
+                    ))
+                })
+                .collect::<Result<_>>()?,
+            MediaType::Tv => sonarr::get_all_sonarr_data(is_4k)
+                .await?
+                .into_iter()
+                .map(|series| Ok((series.id, Self::Tv(TvData::from_resource(series, is_4k)?))))
+                .collect::<Result<_>>()?,
+        };
+
+        Ok(data)
     }
 
-    pub async fn remove_data(self) -> Result<()> {
+    /// The id of the item in its *arr instance.
+    pub fn id(&self) -> i32 {
         match self {
-            Self::Movie(movie) => movie.remove_data().await,
-            Self::Tv(tv) => tv.remove_data().await,
+            Self::Movie(movie) => movie.id,
+            Self::Tv(tv) => tv.id,
         }
     }
 
@@ -74,6 +149,51 @@ impl ArrData {
             Self::Tv(tv) => tv.size_on_disk,
         }
     }
+
+    pub fn has_ended(&self) -> bool {
+        match self {
+            Self::Movie(_) => false,
+            Self::Tv(tv) => matches!(tv.status, SeriesStatus::Ended),
+        }
+    }
+
+    /// The name of the instance the data is from, matching its section in the config.
+    pub fn instance_name(&self) -> &'static str {
+        self.record().instance_name()
+    }
+
+    pub fn record(&self) -> &ArrRecord {
+        match self {
+            Self::Movie(movie) => &movie.record,
+            Self::Tv(tv) => &tv.record,
+        }
+    }
+
+    pub fn last_available_episode(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::Movie(_) => None,
+            Self::Tv(tv) => tv.last_available_episode,
+        }
+    }
+
+    /// Looks up the last episode of a series that has a file, as (season, episode), leaving out
+    /// specials. Sonarr only lists the episodes of one series at a time, so this is done for the
+    /// series that need it rather than while getting everything.
+    pub async fn find_last_available_episode(&mut self) -> Result<()> {
+        let tv = match self {
+            Self::Movie(_) => return Ok(()),
+            Self::Tv(tv) => tv,
+        };
+
+        tv.last_available_episode = sonarr::get_episodes(tv.id, tv.record.is_4k)
+            .await?
+            .into_iter()
+            .filter(|episode| episode.has_file && episode.season_number > 0)
+            .map(|episode| (episode.season_number, episode.episode_number))
+            .max();
+
+        Ok(())
+    }
 }
 
 impl Display for ArrData {
@@ -85,20 +205,31 @@ impl Display for ArrData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct MovieData {
     id: i32,
     status: MovieStatus,
     size_on_disk: i64,
     digital_release: Option<DateTime<Utc>>,
     physical_release: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    record: ArrRecord,
 }
 
 impl MovieData {
-    async fn get_data(id: i32, is_4k: bool) -> Result<Self> {
-        let data = radarr::get_radarr_data(id, is_4k).await?;
-
+    fn from_resource(data: radarr::MovieResource, is_4k: bool) -> Result<Self> {
         Ok(Self {
+            record: ArrRecord {
+                media_type: MediaType::Movie,
+                is_4k,
+                title: data.title.unwrap_or_default(),
+                tmdb_id: Some(data.tmdb_id),
+                tvdb_id: None,
+                quality_profile_id: data.quality_profile_id,
+                language_profile_id: None,
+                root_folder_path: data.root_folder_path,
+                monitored: data.monitored,
+            },
             id: data.id,
             status: data.status,
             size_on_disk: data.size_on_disk,
@@ -106,10 +237,6 @@ impl MovieData {
             physical_release: get_potential_date_time(data.physical_release)?,
         })
     }
-
-    async fn remove_data(self) -> Result<()> {
-        radarr::delete_radarr_data_and_files(self.id).await
-    }
 }
 
 impl Display for MovieData {
@@ -128,7 +255,7 @@ impl Display for MovieData {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TvData {
     id: i32,
     status: SeriesStatus,
@@ -138,16 +265,13 @@ pub struct TvData {
     episodes_in_last_season: i32,
     percent_of_episodes_on_disk: f64,
     size_on_disk: i64,
+    last_available_episode: Option<(u32, u32)>,
+    #[serde(skip)]
+    record: ArrRecord,
 }
 
 impl TvData {
-    async fn remove_data(self) -> Result<()> {
-        sonarr::remove_sonarr_data_and_files(self.id).await
-    }
-
-    async fn get_data(id: i32, is_4k: bool) -> Result<Self> {
-        let data = sonarr::get_sonarr_data(id, is_4k).await?;
-
+    fn from_resource(data: sonarr::SeriesResource, is_4k: bool) -> Result<Self> {
         let episodes_in_last_season = data
             .seasons
             .iter()
@@ -155,17 +279,26 @@ impl TvData {
             .map(|s| s.statistics.episode_count);
 
         Ok(Self {
+            record: ArrRecord {
+                media_type: MediaType::Tv,
+                is_4k,
+                title: data.title.unwrap_or_default(),
+                tmdb_id: None,
+                tvdb_id: Some(data.tvdb_id),
+                quality_profile_id: data.quality_profile_id,
+                language_profile_id: data.language_profile_id,
+                root_folder_path: data.root_folder_path,
+                monitored: data.monitored,
+            },
             id: data.id,
             last_airing: get_potential_date_time(data.previous_airing)?,
             next_airing: get_potential_date_time(data.next_airing)?,
             status: data.status,
             season_count: data.statistics.season_count,
-            episodes_in_last_season: match episodes_in_last_season {
-                Some(count) => count,
-                None => 0,
-            },
+            episodes_in_last_season: episodes_in_last_season.unwrap_or_default(),
             percent_of_episodes_on_disk: data.statistics.percent_of_episodes,
             size_on_disk: data.statistics.size_on_disk,
+            last_available_episode: None,
         })
     }
 }
@@ -1,72 +1,100 @@
 use color_eyre::{eyre::eyre, Result};
-use serde::de::DeserializeOwned;
+use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
+    cache,
     config::{Config, Radarr},
-    utils::{create_api_error_message, create_param_string},
+    error::ServiceError,
+    http,
+    utils::create_param_string,
 };
 
 pub async fn get<T>(path: &str, params: Option<Vec<(&str, &str)>>, is_4k: bool) -> Result<T>
 where
     T: DeserializeOwned,
 {
-    let config: &Radarr;
-    if is_4k {
-        config = match &Config::global().radarr_4k {
-            Some(ref radarr) => radarr,
-            None => {
-                return Err(eyre!(
-                    "Tried to access radarr config, even though it is not defined."
-                ))
-            }
-        }
-    } else {
-        config = match &Config::global().radarr {
-            Some(ref radarr) => radarr,
-            None => {
-                return Err(eyre!(
-                    "Tried to access radarr config, even though it is not defined."
-                ))
-            }
-        };
-    }
+    let global_config = Config::global();
+    let config = instance_config(&global_config, is_4k)?;
 
-    let client = reqwest::Client::new();
+    let instance = if is_4k { "radarr_4k" } else { "radarr" };
+    let client = http::client("radarr", instance, &config.requests);
     let params = create_param_string(params);
 
-    let response = client
-        .get(format!("{}/api/v3{}?{}", config.url, path, params))
+    let url = format!("{}/api/v3{}?{}", config.url, path, params);
+    let body = cache::get_or_fetch("radarr", &url, || async {
+        let request = client.get(&url).header("X-Api-Key", &config.api_key);
+        let response = client.send(request).await?;
+
+        if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+            return Err(ServiceError::from_response("Radarr", path, response)
+                .await
+                .into());
+        }
+
+        client.text(path, response).await
+    })
+    .await?;
+
+    let response = serde_json::from_str(&body)
+        .map_err(|err| ServiceError::parse("Radarr", path, err, &body))?;
+
+    Ok(response)
+}
+
+pub async fn post<T>(path: &str, body: &T, is_4k: bool) -> Result<()>
+where
+    T: Serialize,
+{
+    let global_config = Config::global();
+    let config = instance_config(&global_config, is_4k)?;
+    let instance = if is_4k { "radarr_4k" } else { "radarr" };
+    let client = http::client("radarr", instance, &config.requests);
+
+    let request = client
+        .post(&format!("{}/api/v3{}", &config.url, path))
         .header("X-Api-Key", &config.api_key)
-        .send()
-        .await?;
+        .json(body);
+    let response = client.send(request).await?;
 
     if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
-        let code = response.status().as_u16();
-        return Err(eyre!(create_api_error_message(code, path, "Radarr")));
+        return Err(ServiceError::from_response("Radarr", path, response)
+            .await
+            .into());
     }
 
-    let response = response.json().await?;
-
-    Ok(response)
+    Ok(())
 }
 
-pub async fn delete(path: &str, params: Option<Vec<(&str, &str)>>) -> Result<()> {
-    let config = match &Config::global().radarr {
-        Some(ref radarr) => radarr,
-        None => {
-            return Err(eyre!(
-                "Tried to access radarr config, even though it is not defined."
-            ))
-        }
-    };
-    let client = reqwest::Client::new();
+pub async fn delete(path: &str, params: Option<Vec<(&str, &str)>>, is_4k: bool) -> Result<()> {
+    let global_config = Config::global();
+    let config = instance_config(&global_config, is_4k)?;
+    let instance = if is_4k { "radarr_4k" } else { "radarr" };
+    let client = http::client("radarr", instance, &config.requests);
     let params = create_param_string(params);
 
-    client
-        .delete(format!("{}/api/v3{}?{}", &config.url, path, params))
-        .header("X-Api-Key", &config.api_key)
-        .send()
-        .await?;
+    let request = client
+        .delete(&format!("{}/api/v3{}?{}", &config.url, path, params))
+        .header("X-Api-Key", &config.api_key);
+    let response = client.send(request).await?;
+
+    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+        return Err(ServiceError::from_response("Radarr", path, response)
+            .await
+            .into());
+    }
 
     Ok(())
 }
+
+/// The config of the standard or the 4K instance.
+fn instance_config(config: &Config, is_4k: bool) -> Result<&Radarr> {
+    let instance = if is_4k {
+        &config.radarr_4k
+    } else {
+        &config.radarr
+    };
+
+    instance
+        .as_ref()
+        .ok_or_else(|| eyre!("Tried to access radarr config, even though it is not defined."))
+}
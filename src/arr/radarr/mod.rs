@@ -1,18 +1,62 @@
 mod api;
 mod responses;
 
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
+use serde::Serialize;
 
-use self::responses::MovieResource;
+use super::{ArrRecord, SystemStatus};
+
+pub use self::responses::MovieResource;
 pub use self::responses::MovieStatus;
 
-pub async fn get_radarr_data(id: i32, is_4k: bool) -> Result<MovieResource> {
-    let path = format!("/movie/{}", id.to_string());
-    api::get(&path, None, is_4k).await
+pub async fn get_system_status(is_4k: bool) -> Result<SystemStatus> {
+    api::get("/system/status", None, is_4k).await
+}
+
+pub async fn get_all_radarr_data(is_4k: bool) -> Result<Vec<MovieResource>> {
+    api::get("/movie", None, is_4k).await
 }
 
-pub async fn delete_radarr_data_and_files(radarr_id: i32) -> Result<()> {
-    let path = format!("/movie/{}", radarr_id.to_string());
+pub async fn delete_radarr_data_and_files(radarr_id: i32, is_4k: bool) -> Result<()> {
+    let path = format!("/movie/{}", radarr_id);
     let params = vec![("deleteFiles", "true"), ("addImportExclusion", "false")];
-    api::delete(path.as_str(), Some(params)).await
+    api::delete(path.as_str(), Some(params), is_4k).await
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddMovie<'a> {
+    title: &'a str,
+    tmdb_id: i32,
+    quality_profile_id: i32,
+    root_folder_path: &'a str,
+    monitored: bool,
+    add_options: AddMovieOptions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddMovieOptions {
+    search_for_movie: bool,
+}
+
+/// Adds a removed movie back with the profile and folder it had, and searches for it.
+pub async fn add_movie(record: &ArrRecord) -> Result<()> {
+    let movie = AddMovie {
+        title: &record.title,
+        tmdb_id: record
+            .tmdb_id
+            .ok_or_else(|| eyre!("The movie can not be added, as its TMDB ID is unknown."))?,
+        quality_profile_id: record.quality_profile_id,
+        root_folder_path: record
+            .root_folder_path
+            .as_deref()
+            .ok_or_else(|| eyre!("The movie can not be added, as its root folder is unknown."))?,
+        monitored: record.monitored,
+        add_options: AddMovieOptions {
+            search_for_movie: true,
+        },
+    };
+
+    api::post("/movie", &movie, record.is_4k).await
 }
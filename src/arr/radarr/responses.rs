@@ -1,17 +1,21 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MovieResource {
     pub id: i32,
     pub title: Option<String>,
+    pub tmdb_id: i32,
+    pub quality_profile_id: i32,
+    pub root_folder_path: Option<String>,
+    pub monitored: bool,
     pub status: MovieStatus,
     pub size_on_disk: i64,
     pub digital_release: Option<String>,
     pub physical_release: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "camelCase")]
 pub enum MovieStatus {
     #[serde(rename = "tba")]
@@ -1,18 +1,76 @@
 mod api;
 mod responses;
 
-use color_eyre::Result;
+use color_eyre::{eyre::eyre, Result};
+use serde::Serialize;
 
-use self::responses::SeriesResource;
+use super::{ArrRecord, SystemStatus};
+
+pub use self::responses::EpisodeResource;
+pub use self::responses::SeriesResource;
 pub use self::responses::SeriesStatus;
 
-pub async fn get_sonarr_data(id: i32, is_4k: bool) -> Result<SeriesResource> {
-    let path = format!("/series/{}", id.to_string());
-    api::get(&path, None, is_4k).await
+pub async fn get_system_status(is_4k: bool) -> Result<SystemStatus> {
+    api::get("/system/status", None, is_4k).await
+}
+
+pub async fn get_all_sonarr_data(is_4k: bool) -> Result<Vec<SeriesResource>> {
+    api::get("/series", None, is_4k).await
 }
 
-pub async fn remove_sonarr_data_and_files(sonarr_id: i32) -> Result<()> {
-    let path = format!("/series/{}", sonarr_id.to_string());
+pub async fn get_episodes(series_id: i32, is_4k: bool) -> Result<Vec<EpisodeResource>> {
+    let series_id = series_id.to_string();
+    api::get("/episode", Some(vec![("seriesId", &series_id)]), is_4k).await
+}
+
+pub async fn remove_sonarr_data_and_files(sonarr_id: i32, is_4k: bool) -> Result<()> {
+    let path = format!("/series/{}", sonarr_id);
     let params = vec![("deleteFiles", "true"), ("addImportListExclusion", "false")];
-    api::delete(path.as_str(), Some(params)).await
+    api::delete(path.as_str(), Some(params), is_4k).await
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddSeries<'a> {
+    title: &'a str,
+    tvdb_id: i32,
+    quality_profile_id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_profile_id: Option<i32>,
+    root_folder_path: &'a str,
+    monitored: bool,
+    season_folder: bool,
+    add_options: AddSeriesOptions,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddSeriesOptions {
+    monitor: &'static str,
+    search_for_missing_episodes: bool,
+}
+
+/// Adds a removed series back with the profiles and folder it had, and searches for all of its
+/// episodes.
+pub async fn add_series(record: &ArrRecord) -> Result<()> {
+    let series = AddSeries {
+        title: &record.title,
+        tvdb_id: record
+            .tvdb_id
+            .ok_or_else(|| eyre!("The series can not be added, as its TVDB ID is unknown."))?,
+        quality_profile_id: record.quality_profile_id,
+        language_profile_id: record.language_profile_id,
+        root_folder_path: record
+            .root_folder_path
+            .as_deref()
+            .ok_or_else(|| eyre!("The series can not be added, as its root folder is unknown."))?,
+        monitored: record.monitored,
+        season_folder: true,
+        add_options: AddSeriesOptions {
+            monitor: "all",
+            search_for_missing_episodes: true,
+        },
+    };
+
+    api::post("/series", &series, record.is_4k).await
 }
@@ -1,10 +1,15 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct SeriesResource {
     pub id: i32,
     pub title: Option<String>,
+    pub tvdb_id: i32,
+    pub quality_profile_id: i32,
+    pub language_profile_id: Option<i32>,
+    pub root_folder_path: Option<String>,
+    pub monitored: bool,
     pub status: SeriesStatus,
     pub previous_airing: Option<String>,
     pub next_airing: Option<String>,
@@ -12,7 +17,7 @@ pub struct SeriesResource {
     pub seasons: Vec<SeasonResource>,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum SeriesStatus {
     Continuing,
@@ -25,8 +30,6 @@ pub enum SeriesStatus {
 #[serde(rename_all = "camelCase")]
 pub struct SeriesStatisticsResource {
     pub season_count: i32,
-    pub episode_file_count: i32,
-    pub episode_count: i32,
     pub size_on_disk: i64,
     pub percent_of_episodes: f64,
 }
@@ -43,3 +46,11 @@ pub struct SeasonResource {
 pub struct SeasonStatisticsResource {
     pub episode_count: i32,
 }
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeResource {
+    pub season_number: u32,
+    pub episode_number: u32,
+    pub has_file: bool,
+}
@@ -0,0 +1,154 @@
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    future::Future,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use crate::config::Config;
+
+static TEMP_FILE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+static BYPASSED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    fetched_at: DateTime<Utc>,
+    body: String,
+}
+
+/// The directory where responses from the services are cached.
+/// Uses `cache.dir` from the config if set, otherwise the platform's cache directory.
+pub fn cache_dir() -> PathBuf {
+    match Config::global().cache.dir {
+        Some(ref dir) => PathBuf::from(dir),
+        None => dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("media-cleaner"),
+    }
+}
+
+/// Returns the cached response body for the key if it is recent enough, otherwise fetches and
+/// caches it. The key should identify the request without including any secrets.
+///
+/// The cache is only an optimisation, so any problem reading or writing it is treated as a miss.
+pub async fn get_or_fetch<F, Fut>(service: &str, key: &str, fetch: F) -> Result<String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let ttl_hours = Config::global().cache.ttl_hours;
+    if ttl_hours == 0 || BYPASSED.load(Ordering::Relaxed) {
+        return fetch().await;
+    }
+
+    let path = entry_path(service, key);
+    if let Some(body) = read_entry(&path, key, ttl_hours) {
+        return Ok(body);
+    }
+
+    let body = fetch().await?;
+    let _ = write_entry(&path, key, &body);
+
+    Ok(body)
+}
+
+/// Sends every request to the services for the rest of the run, leaving the cache as it is.
+pub fn bypass() {
+    BYPASSED.store(true, Ordering::Relaxed);
+}
+
+/// Removes the cached responses, so the next requests go to the services again.
+///
+/// The cache directory can be any directory the user picked, so only the files the cache wrote
+/// itself are removed, and the directory is left in place.
+pub fn clear() -> Result<()> {
+    let dir = cache_dir();
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for service_dir in fs::read_dir(dir)? {
+        let service_dir = service_dir?.path();
+        if !service_dir.is_dir() {
+            continue;
+        }
+
+        for file in fs::read_dir(&service_dir)? {
+            let file = file?.path();
+            if file.is_file() && is_entry_file(&file) {
+                fs::remove_file(file)?;
+            }
+        }
+        // Only succeeds when nothing but the cache was in there.
+        let _ = fs::remove_dir(service_dir);
+    }
+
+    Ok(())
+}
+
+/// Whether the file is named like an entry, or the temporary file of one, from `entry_path`.
+fn is_entry_file(path: &Path) -> bool {
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let hash = match name.split_once('.') {
+        Some((hash, "json")) => hash,
+        Some((hash, extension)) if extension.ends_with(".tmp") => hash,
+        _ => return false,
+    };
+
+    hash.len() == 16 && hash.chars().all(|char| char.is_ascii_hexdigit())
+}
+
+fn entry_path(service: &str, key: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+
+    cache_dir()
+        .join(service)
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+fn read_entry(path: &PathBuf, key: &str, ttl_hours: u64) -> Option<String> {
+    let reader = fs::File::open(path).ok()?;
+    let entry: CacheEntry = serde_json::from_reader(reader).ok()?;
+
+    let expires_at = entry.fetched_at + Duration::hours(ttl_hours as i64);
+    if entry.key != key || expires_at < Utc::now() {
+        return None;
+    }
+
+    Some(entry.body)
+}
+
+/// Writes the entry next to its final location and moves it into place, so concurrent readers
+/// never see a half written file.
+fn write_entry(path: &PathBuf, key: &str, body: &str) -> Result<()> {
+    let entry = CacheEntry {
+        key: key.to_string(),
+        fetched_at: Utc::now(),
+        body: body.to_string(),
+    };
+
+    let temp_path = path.with_extension(format!(
+        "{}-{}.tmp",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(&temp_path, serde_json::to_vec(&entry)?)?;
+    fs::rename(temp_path, path)?;
+
+    Ok(())
+}
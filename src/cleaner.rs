@@ -0,0 +1,74 @@
+use color_eyre::{eyre::eyre, Report, Result};
+use once_cell::sync::Lazy;
+use std::{path::Path, sync::Arc};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{
+    config::Config,
+    deletion::{self, DeletionResult},
+    gather,
+    media_item::CompleteMediaItem,
+    notifications::RunMode,
+};
+
+/// Held by the cleaner whose config is the current one.
+static ACTIVE: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Gathers and deletes media for another program, with a config it hands over instead of the
+/// `config.yaml` the command line reads.
+///
+/// The services are reached through process-wide clients, so every call makes the config of the
+/// cleaner the current one for as long as it runs. Calls from different cleaners wait for each
+/// other instead of running at the same time.
+#[derive(Debug, Clone)]
+pub struct MediaCleaner {
+    config: Arc<Config>,
+}
+
+impl MediaCleaner {
+    /// A cleaner with a config read through [`Config::from_yaml`] or [`Config::from_file`], the
+    /// only ways to get one outside of this crate. A config with `discover_servers` on has to go
+    /// through [`Config::discover_servers`] first.
+    pub fn new(config: Config) -> Result<Self> {
+        if config.needs_discovery() {
+            return Err(eyre!("The config has discover_servers on, but the servers have not been looked up yet. Please call Config::discover_servers before creating the cleaner."));
+        }
+
+        Ok(Self {
+            config: Arc::new(config.validated()?),
+        })
+    }
+
+    /// A cleaner with the config in the file, e.g. a `config.yaml` written for the command line.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new(Config::from_file(path)?)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Gathers every available request, or with `all_items` every available item on the server.
+    /// Items that could not be completed are returned as errors next to the ones that could.
+    pub async fn gather(&self, all_items: bool) -> Result<(Vec<CompleteMediaItem>, Vec<Report>)> {
+        let _active = self.activate().await;
+
+        gather::gather_items(all_items).await
+    }
+
+    /// Removes the items from every service, the same way a deletion from the command line does,
+    /// including the deletion plan, the audit log and the notifications.
+    pub async fn delete(&self, items: Vec<CompleteMediaItem>) -> Result<DeletionResult> {
+        let _active = self.activate().await;
+
+        deletion::delete_items(items, RunMode::Library, |_| ()).await
+    }
+
+    /// Makes the config of the cleaner the current one until the guard is dropped.
+    async fn activate(&self) -> MutexGuard<'static, ()> {
+        let guard = ACTIVE.lock().await;
+        Config::set_global(self.config.clone());
+
+        guard
+    }
+}
@@ -0,0 +1,397 @@
+use chrono::Local;
+use color_eyre::{eyre::eyre, Report, Result};
+use dialoguer::MultiSelect;
+use itertools::Itertools;
+use std::{io, process::Command, sync::Arc};
+
+use crate::{
+    arguments::{self, Arguments},
+    cache,
+    config::Config,
+    daemon,
+    deletion::{self, DeletionPlan},
+    doctor,
+    error, export, gather,
+    media_item::CompleteMediaItem,
+    notifications::RunMode,
+    report, requesters, restore, server,
+    shared::SortingOption,
+    utils::human_file_size,
+};
+
+/// Runs the command given in the arguments. Commands that need the config read it from
+/// `--config`, or the first config file found, with the overrides from the environment.
+pub async fn run() -> Result<()> {
+    Arguments::read_args();
+    let command = Arguments::get_args().command;
+
+    if command.needs_config() {
+        // Looking up the servers contacts Overseerr, which validating the config should not do.
+        // The doctor looks them up itself, to report it when that fails.
+        read_and_validate_config(!matches!(
+            command,
+            arguments::Command::ValidateConfig | arguments::Command::Doctor
+        ))
+        .await?;
+
+        // The cache directory can be set in the config.
+        if Arguments::get_args().refresh {
+            cache::clear()?;
+        }
+    }
+
+    match command {
+        arguments::Command::Daemon => return daemon::run().await,
+        arguments::Command::Serve => return server::run().await,
+        arguments::Command::Report => return report::run().await,
+        arguments::Command::List => return export::list().await,
+        arguments::Command::Export => return export::export().await,
+        arguments::Command::ValidateConfig => {
+            match Config::find_file(Arguments::get_args().config.as_deref()) {
+                Some(path) => println!("The config at {} is valid.", path.display()),
+                None => println!("The config from the environment is valid."),
+            }
+            return Ok(());
+        }
+        arguments::Command::Doctor => return doctor::run().await,
+        arguments::Command::Resume => return resume_deletion().await,
+        arguments::Command::Restore => return restore::run().await,
+        arguments::Command::Completions(shell) => {
+            arguments::print_completions(shell);
+            return Ok(());
+        }
+        arguments::Command::Man => return arguments::print_man_page(),
+        arguments::Command::Clean => (),
+    }
+
+    if DeletionPlan::load()?.is_some() {
+        return Err(eyre!("An earlier deletion was interrupted before it finished. Please finish it with \"media-cleaner resume\" first."));
+    }
+
+    let deletion_items = get_deletion_items().await?;
+
+    show_requests_result(&deletion_items)?;
+
+    clear_screen()?;
+
+    let sorted_requests = choose_sorting(deletion_items)?;
+
+    let chosen_indexes = choose_items_to_delete(&sorted_requests)?;
+
+    delete_chosen_items(sorted_requests, chosen_indexes).await?;
+
+    Ok(())
+}
+
+async fn read_and_validate_config(discover_servers: bool) -> Result<()> {
+    let config = match Config::load(Arguments::get_args().config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => return Err(eyre!("Failed to read the config, with the following error: {}.\nPlease make sure all fields are filled.", err)),
+    };
+
+    let config = if discover_servers {
+        config.discover_servers().await?
+    } else {
+        config
+    };
+    Config::set_global(Arc::new(config));
+
+    Ok(())
+}
+
+async fn get_deletion_items() -> Result<Vec<CompleteMediaItem>> {
+    println!("Gathering all required data from your services.\nDepending on the amount of data and your connection speed, this could take a while...");
+
+    let all_items = Arguments::get_args().all_media;
+
+    let (complete_items, errors) = gather::gather_items(all_items).await?;
+
+    show_potential_request_errors(errors)?;
+
+    Ok(complete_items)
+}
+
+fn show_potential_request_errors(errs: Vec<Report>) -> Result<()> {
+    if errs.is_empty() {
+        return Ok(());
+    }
+
+    println!("You got {} errors while gathering data:", errs.len());
+    error::group(&errs).iter().for_each(|(cause, count)| {
+        println!("- {} ({})", cause, count);
+    });
+    println!("Press y to show them, or any other input to continue with the errored items ignored.");
+    let input = get_user_input()?;
+    if !input.starts_with("y") {
+        return Ok(());
+    }
+
+    errs.iter().enumerate().for_each(|(i, err)| {
+        println!("Error {} was {}", i, err);
+        print_line();
+    });
+
+    println!("Do you want to see the full stack traces? Press y. Otherwise continuing to deletion screen with errored items ignored.");
+    let inp = get_user_input()?;
+    if !inp.starts_with("y") {
+        return Ok(());
+    }
+
+    errs.iter().enumerate().for_each(|(i, err)| {
+        println!("Error {} was {:?}", i + 1, err);
+        print_line();
+    });
+
+    wait(Some(
+        "Press enter to continue to deletion screen with errored items ignored.",
+    ))?;
+
+    Ok(())
+}
+
+fn show_requests_result(requests: &[CompleteMediaItem]) -> Result<()> {
+    if requests.is_empty() {
+        println!("You do not seem to have any valid requests, with data available.");
+        println!("Are you sure all your requests are available and downloaded? Or some data was unable to be acquired from other services.");
+        println!("Either try again later, or look over your requests.");
+
+        println!();
+        wait(None)?;
+        std::process::exit(0);
+    }
+
+    Ok(())
+}
+
+fn choose_items_to_delete(requests: &[CompleteMediaItem]) -> Result<Vec<usize>> {
+    clear_screen()?;
+
+    show_over_quota_requesters(requests);
+
+    let items_to_show = Config::global().items_shown;
+    let chosen: Vec<usize> = MultiSelect::new()
+        .with_prompt("Choose what media to delete (SPACE to select, ENTER to confirm selection)")
+        .max_length(items_to_show)
+        .items(requests)
+        .interact()?;
+
+    if chosen.is_empty() {
+        println!("No items selected. Exiting...");
+        std::process::exit(0);
+    }
+
+    clear_screen()?;
+
+    verify_chosen(requests, &chosen)?;
+
+    Ok(chosen)
+}
+
+fn show_over_quota_requesters(requests: &[CompleteMediaItem]) {
+    let over_quota = requesters::requester_stats(requests)
+        .into_iter()
+        .filter(|stats| stats.over_quota)
+        .collect_vec();
+    if over_quota.is_empty() {
+        return;
+    }
+
+    println!("These requesters are over their quota, so their unwatched requests are shown first:");
+    over_quota.iter().for_each(|stats| {
+        println!(
+            "- {}: {} of {}, {:.0}% watched by themselves.",
+            stats.name,
+            human_file_size(stats.quota_used),
+            human_file_size(stats.quota.unwrap_or_default()),
+            stats.watched_by_requester_share * 100.0
+        );
+    });
+    println!();
+}
+
+fn choose_sorting(mut requests: Vec<CompleteMediaItem>) -> Result<Vec<CompleteMediaItem>> {
+    clear_screen()?;
+    let args = Arguments::get_args();
+
+    let sort = match args.sorting {
+        Some(ref sort) => sort.clone(),
+        None => choose_sorting_dialogue()?,
+    };
+
+    gather::sort_items(&mut requests, &sort);
+
+    Ok(requests)
+}
+
+fn choose_sorting_dialogue() -> Result<SortingOption> {
+    loop {
+        println!("Choose sorting method:");
+        println!("Name - Ascending: n (or just enter, it's the default)");
+        println!("Name - Descending: nd");
+        println!("Size - Descending: s");
+        println!("Size - Ascending: sa");
+        println!("Type - Descending: t");
+        println!("Requested Date - Ascending: r");
+        println!("Requested Date - Descending: rd");
+
+        let input = get_user_input()?;
+
+        if let Ok(sort) = SortingOption::from_str(&input) {
+            return Ok(sort);
+        }
+        if input.is_empty() {
+            return Ok(SortingOption::default());
+        }
+    }
+}
+
+fn verify_chosen(requests: &[CompleteMediaItem], chosen: &[usize]) -> Result<()> {
+    let total_size: String = human_file_size(
+        chosen
+            .iter()
+            .filter_map(|selection| {
+                requests.get(*selection).map(|media_item| media_item.get_disk_size())
+            })
+            .sum(),
+    );
+
+    println!(
+        "Are you sure you want to delete the following items ({}):",
+        total_size
+    );
+    chosen.iter().for_each(|selection| {
+        if let Some(media_item) = requests.get(*selection) {
+            let media_type = media_item.media_type;
+            println!("- {} - {}", &media_item.title, media_type);
+        } else {
+            println!("- Unknown item");
+        }
+    });
+
+    println!("\ny/n:");
+    let user_input = get_user_input()?;
+
+    if !user_input.starts_with("y") {
+        println!("Cancelling...");
+        std::process::exit(0);
+    }
+
+    Ok(())
+}
+
+async fn delete_chosen_items(
+    mut requests: Vec<CompleteMediaItem>,
+    chosen: Vec<usize>,
+) -> Result<()> {
+    let chosen_items = chosen
+        .into_iter()
+        .rev()
+        .map(|selection| requests.swap_remove(selection))
+        .collect();
+
+    let result = deletion::delete_items(chosen_items, RunMode::Interactive, |_| ()).await?;
+
+    show_notification_errors(&result.notification_errors);
+
+    // If there are no errors, return early
+    if result.errors.is_empty() {
+        return Ok(());
+    }
+
+    show_deletion_errors(&result.errors);
+
+    wait(None)?;
+    Ok(())
+}
+
+async fn resume_deletion() -> Result<()> {
+    let plan = match DeletionPlan::load()? {
+        Some(plan) => plan,
+        None => {
+            println!("There is no interrupted deletion to resume.");
+            return Ok(());
+        }
+    };
+
+    println!(
+        "Resuming the deletion started at {}, which still has to remove:",
+        plan.created_at.with_timezone(&Local).format("%d-%m-%Y %H:%M:%S")
+    );
+    for item in plan.items.iter() {
+        let remaining = item.remaining_services();
+        if !remaining.is_empty() {
+            println!("- {} from {}", item.summary().title, remaining.join(", "));
+        }
+    }
+    print_line();
+
+    let result = deletion::resume(plan, |_| ()).await;
+
+    println!("Finished the deletion, {} items were removed.", result.deleted.len());
+    show_notification_errors(&result.notification_errors);
+    show_deletion_errors(&result.errors);
+
+    Ok(())
+}
+
+fn show_deletion_errors(errs: &[(String, Report)]) {
+    if errs.is_empty() {
+        return;
+    }
+
+    println!("Had some errors deleting items:\n");
+    errs.iter().for_each(|(title, err)| {
+        println!("Got the following error while deleting {}: {}", title, err);
+        print_line();
+    });
+}
+
+fn show_notification_errors(errs: &[(String, Report)]) {
+    if errs.is_empty() {
+        return;
+    }
+
+    println!("Had some errors sending notifications:\n");
+    errs.iter().for_each(|(recipient, err)| {
+        println!("Got the following error while notifying {}: {}", recipient, err);
+        print_line();
+    });
+}
+
+fn clear_screen() -> Result<()> {
+    if cfg!(target_os = "windows") {
+        Command::new("cmd").arg("/C").arg("cls").status()?;
+        Ok(())
+    } else {
+        Command::new("clear").status()?;
+        Ok(())
+    }
+}
+
+fn get_user_input() -> Result<String> {
+    let mut user_input = String::new();
+    let stdin = io::stdin();
+
+    stdin.read_line(&mut user_input)?;
+    user_input = user_input.to_lowercase();
+
+    Ok(user_input
+        .strip_suffix("\r\n")
+        .or(user_input.strip_suffix("\n"))
+        .unwrap_or(&user_input)
+        .to_string())
+}
+
+fn wait(custom_msg: Option<&str>) -> Result<()> {
+    if let Some(msg) = custom_msg {
+        println!("{}", msg);
+    } else {
+        println!("Press enter to continue.");
+    }
+    get_user_input()?;
+    Ok(())
+}
+
+fn print_line() {
+    println!("-----------------------------------------------------------------------------");
+}
@@ -1,9 +1,50 @@
-use color_eyre::Result;
-use once_cell::sync::OnceCell;
+use color_eyre::{
+    eyre::{eyre, WrapErr},
+    Result,
+};
+use itertools::Itertools;
 use serde::Deserialize;
-use std::fs;
+use serde_yaml::{Mapping, Value};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+use crate::{
+    notifications::WebhookFormat,
+    overseerr::{self, ArrServer},
+    shared::MediaType,
+    users::UserIdentity,
+};
+
+static INSTANCE: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+/// The start of the environment variables that override fields of the config, followed by the
+/// path to the field with `__` between the parts, e.g. `MEDIA_CLEANER_SONARR__API_KEY`.
+const ENV_PREFIX: &str = "MEDIA_CLEANER_";
+/// The environment variable holding the path to the config, which is not a field of it.
+pub const CONFIG_PATH_VAR: &str = "MEDIA_CLEANER_CONFIG";
+/// The fields that only hold text. Their environment values are never read as YAML, so a key of
+/// only digits stays a string even when the file doesn't have the field. Fields ending in `_file`
+/// are paths, and are kept as text as well.
+const STRING_FIELDS: &[&str] = &[
+    "url",
+    "token",
+    "api_key",
+    "webhook_url",
+    "host",
+    "username",
+    "password",
+    "from",
+    "dir",
+    "data_dir",
+    "address",
+    "metrics_address",
+    "schedule",
+];
 
-static INSTANCE: OnceCell<Config> = OnceCell::new();
 #[derive(Debug, Deserialize)]
 pub struct Config {
     #[serde(default = "default_items_shown")]
@@ -17,59 +58,541 @@ pub struct Config {
     pub radarr: Option<Radarr>,
     pub radarr_4k: Option<Radarr>,
     pub ignored_users: Option<Vec<String>>,
+    pub requester_policy: Option<RequesterPolicy>,
+    pub requester_quota: Option<RequesterQuota>,
+    pub requester_notifications: Option<RequesterNotifications>,
+    pub webhooks: Option<Vec<Webhook>>,
+    pub data_dir: Option<String>,
+    #[serde(default)]
+    pub cache: Cache,
+    pub daemon: Option<Daemon>,
+    pub server: Option<Server>,
+    /// Whether the Radarr and Sonarr servers have been looked up in Overseerr.
+    #[serde(skip)]
+    discovered: bool,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Plex {
     pub url: String,
+    #[serde(default)]
     pub token: String,
+    /// A file holding the token, e.g. a Docker secret, used instead of `token`.
+    pub token_file: Option<String>,
+    #[serde(flatten)]
+    pub requests: RequestSettings,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Seerr {
     pub url: String,
+    #[serde(default)]
     pub api_key: String,
+    pub api_key_file: Option<String>,
+    /// Use the Radarr and Sonarr servers set up in Overseerr, for the sections not in the config.
+    #[serde(default)]
+    pub discover_servers: bool,
+    #[serde(flatten)]
+    pub requests: RequestSettings,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Tautulli {
     pub url: String,
+    #[serde(default)]
     pub api_key: String,
+    pub api_key_file: Option<String>,
+    #[serde(flatten)]
+    pub requests: RequestSettings,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Sonarr {
+    #[serde(default)]
     pub api_key: String,
+    pub api_key_file: Option<String>,
     pub url: String,
+    #[serde(flatten)]
+    pub requests: RequestSettings,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Radarr {
+    #[serde(default)]
     pub api_key: String,
+    pub api_key_file: Option<String>,
     pub url: String,
+    #[serde(flatten)]
+    pub requests: RequestSettings,
 }
 
-impl Config {
-    pub fn global() -> &'static Config {
-        INSTANCE.get().expect("Config has not been initialized.")
+/// How requests are made to a single service, for servers that are slow or can't keep up.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RequestSettings {
+    #[serde(default = "default_max_concurrent_requests")]
+    pub max_concurrent_requests: usize,
+    pub requests_per_second: Option<f64>,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+impl Default for RequestSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: default_max_concurrent_requests(),
+            requests_per_second: None,
+            timeout_seconds: default_timeout_seconds(),
+            max_retries: default_max_retries(),
+        }
     }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequesterPolicy {
+    #[serde(default = "default_min_progress")]
+    pub min_progress: u8,
+    #[serde(default = "default_hold_days")]
+    pub hold_days: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequesterQuota {
+    pub default_gb: Option<f64>,
+    #[serde(default)]
+    pub users: HashMap<String, f64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequesterNotifications {
+    pub smtp: Option<Smtp>,
+    pub webhook_url: Option<String>,
+    #[serde(default = "default_timeout_seconds")]
+    pub webhook_timeout_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Smtp {
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub password_file: Option<String>,
+    pub from: String,
+    #[serde(default)]
+    pub security: SmtpSecurity,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    #[default]
+    StartTls,
+    Tls,
+    None,
+}
 
-    pub fn read_conf() -> Result<()> {
-        if let Some(_) = INSTANCE.get() {
-            return Ok(());
+#[derive(Debug, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Cache {
+    #[serde(default = "default_cache_ttl_hours")]
+    pub ttl_hours: u64,
+    pub dir: Option<String>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            ttl_hours: default_cache_ttl_hours(),
+            dir: None,
         }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Server {
+    #[serde(default = "default_server_address")]
+    pub address: String,
+    /// When set, the API only changes anything for requests with it as their bearer token.
+    pub token: Option<String>,
+    pub token_file: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Daemon {
+    pub schedule: String,
+    #[serde(default)]
+    pub mode: DaemonMode,
+    #[serde(default = "default_grace_days")]
+    pub grace_days: i64,
+    #[serde(default)]
+    pub all_media: bool,
+    pub metrics_address: Option<String>,
+    pub rules: Rules,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonMode {
+    #[default]
+    Queue,
+    Delete,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct Rules {
+    pub media_type: Option<MediaType>,
+    pub min_request_age_days: Option<i64>,
+    pub unwatched_days: Option<i64>,
+    pub min_size_gb: Option<f64>,
+    #[serde(default)]
+    pub only_fulfilled: bool,
+}
+
+impl Rules {
+    pub fn is_empty(&self) -> bool {
+        self.media_type.is_none()
+            && self.min_request_age_days.is_none()
+            && self.unwatched_days.is_none()
+            && self.min_size_gb.is_none()
+            && !self.only_fulfilled
+    }
+}
 
-        let reader = fs::File::open("config.yaml")?;
-        let mut conf: Config = serde_yaml::from_reader(reader)?;
+impl RequesterQuota {
+    /// The quota of the user in bytes, if they have one.
+    pub fn quota_for(&self, user: &UserIdentity) -> Option<i64> {
+        self.users
+            .iter()
+            .find(|(name, _)| user.has_name(name))
+            .map(|(_, quota)| *quota)
+            .or(self.default_gb)
+            .map(|quota| (quota * 1000000000.0) as i64)
+    }
+}
 
-        Self::clean_urls(&mut conf);
+impl Config {
+    /// The current config, if one has been read yet.
+    pub fn try_global() -> Option<Arc<Config>> {
+        INSTANCE
+            .read()
+            .expect("Config lock has been poisoned.")
+            .clone()
+    }
 
+    pub fn global() -> Arc<Config> {
         INSTANCE
-            .set(conf)
-            .expect("Config has already been initialized.");
+            .read()
+            .expect("Config lock has been poisoned.")
+            .clone()
+            .expect("Config has not been initialized.")
+    }
+
+    /// Reads the config from the path, or from the first of `config_locations` that exists,
+    /// replacing the current config if it succeeds. Anything already holding the old config keeps
+    /// using it until it is done.
+    pub fn read_conf(path: Option<&Path>) -> Result<()> {
+        Self::set_global(Arc::new(Self::load(path)?));
+        Ok(())
+    }
+
+    /// Reads the config like `read_conf`, then looks up the Radarr and Sonarr servers in
+    /// Overseerr if it is set up to.
+    pub async fn read_conf_with_servers(path: Option<&Path>) -> Result<()> {
+        let conf = Self::load(path)?.discover_servers().await?;
+        Self::set_global(Arc::new(conf));
+        Ok(())
+    }
+
+    /// Fills in the Radarr and Sonarr sections from the servers in Overseerr when
+    /// `discover_servers` is on, otherwise it is returned as it is. Sections in the config are
+    /// kept, so a server can still be reached at another URL than the one Overseerr uses.
+    pub async fn discover_servers(mut self) -> Result<Self> {
+        if !self.seerr.discover_servers || self.discovered {
+            return Ok(self);
+        }
+
+        let radarr = overseerr::get_arr_servers(&self.seerr, MediaType::Movie)
+            .await
+            .wrap_err("Could not get the Radarr servers from Overseerr")?;
+        let sonarr = overseerr::get_arr_servers(&self.seerr, MediaType::Tv)
+            .await
+            .wrap_err("Could not get the Sonarr servers from Overseerr")?;
+
+        if self.radarr.is_none() {
+            self.radarr = only_server(&radarr, false, "radarr")?.map(Radarr::from_server);
+        }
+        if self.radarr_4k.is_none() {
+            self.radarr_4k = only_server(&radarr, true, "radarr_4k")?.map(Radarr::from_server);
+        }
+        if self.sonarr.is_none() {
+            self.sonarr = only_server(&sonarr, false, "sonarr")?.map(Sonarr::from_server);
+        }
+        if self.sonarr_4k.is_none() {
+            self.sonarr_4k = only_server(&sonarr, true, "sonarr_4k")?.map(Sonarr::from_server);
+        }
+
+        self.discovered = true;
+        Self::clean_urls(&mut self);
+        if let (None, None) = (&self.radarr, &self.sonarr) {
+            return Err(eyre!("Overseerr has no Sonarr or Radarr servers set up, and none are configured. Application can't continue without at least one of these."));
+        }
+
+        Ok(self)
+    }
+
+    /// Whether the config still has to go through `discover_servers` before it can be used.
+    pub(crate) fn needs_discovery(&self) -> bool {
+        self.seerr.discover_servers && !self.discovered
+    }
+
+    /// The config file that `read_conf` reads, if there is one.
+    pub fn find_file(path: Option<&Path>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => config_locations().into_iter().find(|path| path.is_file()),
+        }
+    }
+
+    /// Reads the config file with the overrides from the environment applied. Without a config
+    /// file, the whole config can come from the environment.
+    pub(crate) fn load(path: Option<&Path>) -> Result<Self> {
+        let overrides = env_overrides();
+
+        let mut conf = match Self::find_file(path) {
+            Some(path) => {
+                let contents = fs::read_to_string(&path)
+                    .map_err(|err| eyre!("Could not read {}: {}", path.display(), err))?;
+                serde_yaml::from_str(&contents)?
+            }
+            None if !overrides.is_empty() => Value::Null,
+            None => {
+                return Err(eyre!(
+                    "No config file found, looked for {}",
+                    config_locations()
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .join(", ")
+                ))
+            }
+        };
+
+        for (keys, value) in overrides {
+            apply_override(&mut conf, &keys, value);
+        }
+
+        let conf: Config = serde_yaml::from_value(conf)?;
+        conf.validated()
+    }
+
+    /// Reads and validates a config file, without making it the current config.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let reader = fs::File::open(path)?;
+        let conf: Config = serde_yaml::from_reader(reader)?;
+
+        conf.validated()
+    }
+
+    /// Parses and validates a config, without making it the current config.
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let conf: Config = serde_yaml::from_str(yaml)?;
+
+        conf.validated()
+    }
+
+    /// Makes the config the one used for every request to the services.
+    pub(crate) fn set_global(conf: Arc<Config>) {
+        *INSTANCE.write().expect("Config lock has been poisoned.") = Some(conf);
+    }
+
+    pub(crate) fn validated(mut self) -> Result<Self> {
+        self.read_secret_files()?;
+        Self::clean_urls(&mut self);
+        self.validate()?;
+
+        Ok(self)
+    }
+
+    fn validate(&self) -> Result<()> {
+        // With discovery on, the servers can all come from Overseerr, which is checked once they
+        // have been looked up.
+        if let (None, None, false) = (&self.radarr, &self.sonarr, self.seerr.discover_servers) {
+            return Err(eyre!("You have not configured Sonarr or Radarr. Application can't continue without at least one of these."));
+        }
+
+        for (service, settings) in self.request_settings() {
+            if settings.max_concurrent_requests == 0 {
+                return Err(eyre!(
+                    "max_concurrent_requests for {} has to be at least 1.",
+                    service
+                ));
+            }
+
+            if settings
+                .requests_per_second
+                .is_some_and(|limit| limit <= 0.0)
+            {
+                return Err(eyre!(
+                    "requests_per_second for {} has to be more than 0.",
+                    service
+                ));
+            }
+        }
+
+        if let Some(ref daemon) = self.daemon {
+            if daemon.rules.is_empty() {
+                return Err(eyre!("The daemon has no rules configured, which would make it remove everything. Please add at least one rule."));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The request settings of every configured service, by their section in the config.
+    fn request_settings(&self) -> Vec<(&'static str, &RequestSettings)> {
+        let mut settings = vec![
+            ("plex", &self.plex.requests),
+            ("overseerr", &self.seerr.requests),
+            ("tautulli", &self.tautulli.requests),
+        ];
+        if let Some(ref sonarr) = self.sonarr {
+            settings.push(("sonarr", &sonarr.requests));
+        }
+
+        if let Some(ref sonarr) = self.sonarr_4k {
+            settings.push(("sonarr_4k", &sonarr.requests));
+        }
+
+        if let Some(ref radarr) = self.radarr {
+            settings.push(("radarr", &radarr.requests));
+        }
+
+        if let Some(ref radarr) = self.radarr_4k {
+            settings.push(("radarr_4k", &radarr.requests));
+        }
+
+        settings
+    }
+
+    /// Reads the `*_file` fields into the secrets they hold, and checks every secret is set.
+    fn read_secret_files(&mut self) -> Result<()> {
+        read_secret("plex", "token", &mut self.plex.token, &self.plex.token_file)?;
+        read_secret(
+            "overseerr",
+            "api_key",
+            &mut self.seerr.api_key,
+            &self.seerr.api_key_file,
+        )?;
+        read_secret(
+            "tautulli",
+            "api_key",
+            &mut self.tautulli.api_key,
+            &self.tautulli.api_key_file,
+        )?;
+
+        for (section, radarr) in [
+            ("radarr", &mut self.radarr),
+            ("radarr_4k", &mut self.radarr_4k),
+        ] {
+            if let Some(radarr) = radarr {
+                read_secret(
+                    section,
+                    "api_key",
+                    &mut radarr.api_key,
+                    &radarr.api_key_file,
+                )?;
+            }
+        }
+
+        for (section, sonarr) in [
+            ("sonarr", &mut self.sonarr),
+            ("sonarr_4k", &mut self.sonarr_4k),
+        ] {
+            if let Some(sonarr) = sonarr {
+                read_secret(
+                    section,
+                    "api_key",
+                    &mut sonarr.api_key,
+                    &sonarr.api_key_file,
+                )?;
+            }
+        }
+
+        let smtp = self
+            .requester_notifications
+            .as_mut()
+            .and_then(|notifications| notifications.smtp.as_mut());
+        if let Some(smtp) = smtp {
+            if let Some(ref path) = smtp.password_file {
+                smtp.password = Some(read_secret_file("smtp", "password", path)?);
+            }
+        }
+
+        if let Some(ref mut server) = self.server {
+            if let Some(ref path) = server.token_file {
+                server.token = Some(read_secret_file("server", "token", path)?);
+            }
+        }
+
         Ok(())
     }
 
+    /// The API keys, tokens and passwords in the config, which should never end up in a message.
+    pub fn secrets(&self) -> Vec<&str> {
+        let mut secrets = vec![
+            self.plex.token.as_str(),
+            self.seerr.api_key.as_str(),
+            self.tautulli.api_key.as_str(),
+        ];
+        secrets.extend(
+            [&self.radarr, &self.radarr_4k]
+                .into_iter()
+                .flatten()
+                .map(|radarr| radarr.api_key.as_str()),
+        );
+        secrets.extend(
+            [&self.sonarr, &self.sonarr_4k]
+                .into_iter()
+                .flatten()
+                .map(|sonarr| sonarr.api_key.as_str()),
+        );
+        secrets.extend(
+            self.requester_notifications
+                .as_ref()
+                .and_then(|notifications| notifications.smtp.as_ref())
+                .and_then(|smtp| smtp.password.as_deref()),
+        );
+        secrets.extend(
+            self.server
+                .as_ref()
+                .and_then(|server| server.token.as_deref()),
+        );
+        // Discord and Slack webhook URLs hold their token.
+        secrets.extend(
+            self.webhooks
+                .iter()
+                .flatten()
+                .map(|webhook| webhook.url.as_str()),
+        );
+        secrets.extend(
+            self.requester_notifications
+                .as_ref()
+                .and_then(|notifications| notifications.webhook_url.as_deref()),
+        );
+
+        secrets.retain(|secret| !secret.is_empty());
+        secrets
+    }
+
     fn clean_urls(conf: &mut Config) {
         clean_url(&mut conf.seerr.url);
         clean_url(&mut conf.plex.url);
@@ -93,12 +616,211 @@ impl Config {
     }
 }
 
+impl Radarr {
+    fn from_server(server: &ArrServer) -> Self {
+        Self {
+            api_key: server.api_key.clone(),
+            api_key_file: None,
+            url: server.url.clone(),
+            requests: RequestSettings::default(),
+        }
+    }
+}
+
+impl Sonarr {
+    fn from_server(server: &ArrServer) -> Self {
+        Self {
+            api_key: server.api_key.clone(),
+            api_key_file: None,
+            url: server.url.clone(),
+            requests: RequestSettings::default(),
+        }
+    }
+}
+
+/// The standard or 4K server for the section. Only one instance of each is cleaned up, so when
+/// Overseerr has several it is up to the config to say which one.
+fn only_server<'a>(
+    servers: &'a [ArrServer],
+    is_4k: bool,
+    section: &str,
+) -> Result<Option<&'a ArrServer>> {
+    let servers = servers
+        .iter()
+        .filter(|server| server.is_4k == is_4k)
+        .collect_vec();
+
+    match servers.as_slice() {
+        [] => Ok(None),
+        [server] => Ok(Some(server)),
+        servers => Err(eyre!(
+            "Overseerr has {} servers that could be {} ({}), but only one of them can be cleaned up. Please set up {} in the config to choose.",
+            servers.len(),
+            section,
+            servers.iter().map(|server| format!("\"{}\"", server.name)).join(", "),
+            section
+        )),
+    }
+}
+
 fn default_items_shown() -> usize {
     5
 }
 
+pub fn default_min_progress() -> u8 {
+    90
+}
+
+fn default_max_concurrent_requests() -> usize {
+    8
+}
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_hold_days() -> i64 {
+    90
+}
+
+fn default_cache_ttl_hours() -> u64 {
+    12
+}
+
+pub fn default_server_address() -> String {
+    "127.0.0.1:8080".to_string()
+}
+
+fn default_grace_days() -> i64 {
+    7
+}
+
 fn clean_url(url: &mut String) {
     if url.ends_with("/") {
         url.pop();
     }
 }
+
+/// Replaces the secret with the contents of the file if there is one, e.g. `api_key` with the
+/// contents of `api_key_file`, and fails if the secret ends up empty.
+fn read_secret(
+    section: &str,
+    field: &str,
+    secret: &mut String,
+    file: &Option<String>,
+) -> Result<()> {
+    if let Some(path) = file {
+        *secret = read_secret_file(section, field, path)?;
+    }
+
+    if secret.is_empty() {
+        return Err(eyre!(
+            "{} needs either {} or {}_file to be set.",
+            section,
+            field,
+            field
+        ));
+    }
+
+    Ok(())
+}
+
+fn read_secret_file(section: &str, field: &str, path: &str) -> Result<String> {
+    let secret = fs::read_to_string(path).map_err(|err| {
+        eyre!(
+            "Could not read the {}_file of {} at {}: {}",
+            field,
+            section,
+            path,
+            err
+        )
+    })?;
+
+    Ok(secret.trim().to_string())
+}
+
+/// Where to look for the config when no path is given, in order: the working directory, the
+/// user's config directory, the XDG config directories and `/etc/media-cleaner`.
+fn config_locations() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".")];
+    dirs.extend(dirs::config_dir().map(|dir| dir.join("media-cleaner")));
+
+    if cfg!(unix) {
+        let xdg_dirs = env::var("XDG_CONFIG_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/etc/xdg".to_string());
+        dirs.extend(
+            xdg_dirs
+                .split(':')
+                .map(|dir| Path::new(dir).join("media-cleaner")),
+        );
+        dirs.push(PathBuf::from("/etc/media-cleaner"));
+    }
+
+    dirs.into_iter()
+        .map(|dir| dir.join("config.yaml"))
+        .unique()
+        .collect()
+}
+
+/// The fields set through the environment, as the path to the field and its value.
+fn env_overrides() -> Vec<(Vec<String>, String)> {
+    env::vars()
+        .filter(|(name, _)| name != CONFIG_PATH_VAR)
+        .filter_map(|(name, value)| {
+            let keys = name
+                .strip_prefix(ENV_PREFIX)?
+                .split("__")
+                .map(str::to_lowercase)
+                .collect_vec();
+            if keys.iter().any(String::is_empty) {
+                return None;
+            }
+
+            Some((keys, value))
+        })
+        .sorted_by(|(a, _), (b, _)| a.cmp(b))
+        .collect()
+}
+
+/// Sets the field at the path, adding the sections leading up to it where needed.
+/// The value is read as YAML, so `true` and `5` are a boolean and a number, and lists can be
+/// given as `[a, b]`. A value replacing a string in the file, or for one of the `STRING_FIELDS`,
+/// stays a string though, so an API key of only digits is not made a number.
+fn apply_override(conf: &mut Value, keys: &[String], value: String) {
+    let is_string_field = keys
+        .last()
+        .is_some_and(|key| STRING_FIELDS.contains(&key.as_str()) || key.ends_with("_file"));
+
+    let mut current = conf;
+    for (depth, key) in keys.iter().enumerate() {
+        if !current.is_mapping() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let mapping = current.as_mapping_mut().expect("Was just made a mapping.");
+
+        // `overseerr` is also read as `seerr`, so use whichever the file already has.
+        let key = match key.as_str() {
+            "overseerr" | "seerr" if depth == 0 => ["seerr", "overseerr"]
+                .into_iter()
+                .find(|name| mapping.contains_key(*name))
+                .unwrap_or(key),
+            _ => key,
+        };
+
+        current = mapping
+            .entry(Value::String(key.to_string()))
+            .or_insert(Value::Null);
+    }
+
+    *current = match current {
+        Value::String(_) => Value::String(value),
+        _ if is_string_field => Value::String(value),
+        _ => serde_yaml::from_str(&value).unwrap_or(Value::String(value)),
+    };
+}
@@ -0,0 +1,214 @@
+mod queue;
+
+use chrono::{DateTime, Local, Utc};
+use color_eyre::{eyre::eyre, Result};
+use cron::Schedule;
+use itertools::Itertools;
+use std::{net::SocketAddr, str::FromStr};
+
+use crate::{
+    arguments::Arguments,
+    cache,
+    config::{Config, DaemonMode},
+    deletion::{self, DeletionPlan, DeletionResult},
+    gather, metrics,
+    notifications::RunMode,
+    rules,
+    utils::shutdown_signal,
+};
+
+/// Runs cleanups on the configured schedule until the process is told to stop.
+/// The config is read again before every cleanup, so it can be changed without a restart.
+pub async fn run() -> Result<()> {
+    let mut shutdown = Box::pin(shutdown_signal());
+
+    if let Some(address) = metrics_address()? {
+        log(&format!("Serving metrics at http://{}/metrics", address));
+        tokio::spawn(async move {
+            if let Err(err) = metrics::serve(address).await {
+                log(&format!("The metrics server stopped: {}", err));
+            }
+        });
+    }
+
+    loop {
+        let next_run = next_run()?;
+        log(&format!(
+            "Next cleanup is scheduled for {}.",
+            next_run.with_timezone(&Local).format("%d-%m-%Y %H:%M:%S")
+        ));
+
+        let wait_time = (next_run - Utc::now()).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait_time) => (),
+            _ = &mut shutdown => {
+                log("Received shutdown signal, stopping.");
+                return Ok(());
+            }
+        }
+
+        if let Err(err) =
+            Config::read_conf_with_servers(Arguments::get_args().config.as_deref()).await
+        {
+            log(&format!(
+                "Failed to reload the config, keeping the previous one: {:#}",
+                err
+            ));
+        }
+
+        let cycle = run_cycle();
+        tokio::pin!(cycle);
+
+        let stopping = tokio::select! {
+            result = &mut cycle => {
+                log_cycle_result(result);
+                false
+            }
+            _ = &mut shutdown => true,
+        };
+
+        if stopping {
+            log("Received shutdown signal, stopping after the current cleanup.");
+            log_cycle_result(cycle.await);
+            return Ok(());
+        }
+    }
+}
+
+fn metrics_address() -> Result<Option<SocketAddr>> {
+    match Config::global().daemon {
+        Some(ref daemon) => match daemon.metrics_address {
+            Some(ref address) => Ok(Some(address.parse()?)),
+            None => Ok(None),
+        },
+        None => Ok(None),
+    }
+}
+
+fn next_run() -> Result<DateTime<Utc>> {
+    let config = Config::global();
+    let daemon = match config.daemon {
+        Some(ref daemon) => daemon,
+        None => return Err(eyre!("The daemon section is missing from the config.")),
+    };
+
+    // Allow the usual five field cron expressions, by adding the seconds the cron crate expects.
+    let expression = match daemon.schedule.split_whitespace().count() {
+        5 => format!("0 {}", daemon.schedule),
+        _ => daemon.schedule.clone(),
+    };
+
+    let schedule = Schedule::from_str(&expression)
+        .map_err(|err| eyre!("Invalid daemon schedule \"{}\": {}", daemon.schedule, err))?;
+
+    schedule
+        .upcoming(Utc)
+        .next()
+        .ok_or_else(|| eyre!("The daemon schedule never runs again."))
+}
+
+async fn run_cycle() -> Result<()> {
+    let config = Config::global();
+    let daemon = match config.daemon {
+        Some(ref daemon) => daemon,
+        None => return Err(eyre!("The daemon section is missing from the config.")),
+    };
+
+    if let Some(plan) = DeletionPlan::load()? {
+        log("Finishing a deletion that was interrupted.");
+        log_deletion_result(&deletion::resume(plan, |_| ()).await);
+    }
+
+    log("Starting scheduled cleanup, gathering data from your services.");
+    // Scheduled cleanups always work with fresh data, refreshing the cache for everything else.
+    cache::clear()?;
+    let (items, errors) = gather::gather_items(daemon.all_media).await?;
+    for err in errors.iter() {
+        log(&format!(
+            "Skipping an item that could not be gathered: {}",
+            err
+        ));
+    }
+
+    let (matching, not_matching): (Vec<_>, Vec<_>) = items
+        .into_iter()
+        .partition(|item| rules::matches(item, &daemon.rules));
+    log(&format!("{} items match the rules.", matching.len()));
+
+    let (to_delete, notification_errors) = match daemon.mode {
+        // Deleting straight away leaves no time to warn anyone, the requesters only hear about
+        // it once it is done. The queue warns them when an item is queued.
+        DaemonMode::Delete => (matching, Vec::new()),
+        DaemonMode::Queue => {
+            let not_matching = not_matching
+                .into_iter()
+                .map(|item| item.rating_key)
+                .collect_vec();
+            let update = queue::update(matching, &not_matching, daemon.grace_days).await?;
+
+            log(&format!(
+                "Queued {} new items, {} items are due for removal.",
+                update.newly_queued,
+                update.due.len()
+            ));
+            for title in update.dropped.iter() {
+                log(&format!(
+                    "{} no longer matches the rules, so it was unqueued.",
+                    title
+                ));
+            }
+
+            (update.due, update.notification_errors)
+        }
+    };
+
+    for (recipient, err) in notification_errors.iter() {
+        log(&format!(
+            "Got the following error while notifying {}: {}",
+            recipient, err
+        ));
+    }
+
+    if !to_delete.is_empty() {
+        let result = deletion::delete_items(to_delete, RunMode::Scheduled, |_| ()).await?;
+
+        if daemon.mode == DaemonMode::Queue {
+            queue::remove_deleted(&result.deleted)?;
+        }
+
+        log_deletion_result(&result);
+    }
+
+    Ok(())
+}
+
+fn log_deletion_result(result: &DeletionResult) {
+    log(&format!(
+        "Deleted {} items, with {} errors.",
+        result.deleted.len(),
+        result.errors.len()
+    ));
+    for (title, err) in result.errors.iter() {
+        log(&format!(
+            "Got the following error while deleting {}: {}",
+            title, err
+        ));
+    }
+    for (recipient, err) in result.notification_errors.iter() {
+        log(&format!(
+            "Got the following error while notifying {}: {}",
+            recipient, err
+        ));
+    }
+}
+
+fn log_cycle_result(result: Result<()>) {
+    match result {
+        Ok(()) => log("Scheduled cleanup finished."),
+        Err(err) => log(&format!("Scheduled cleanup failed: {:#}", err)),
+    }
+}
+
+fn log(message: &str) {
+    println!("[{}] {}", Local::now().format("%d-%m-%Y %H:%M:%S"), message);
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Duration, Utc};
+use color_eyre::{Report, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    media_item::CompleteMediaItem,
+    notifications::{self, ItemSummary, RemovalEvent, RequesterNotice},
+    state,
+};
+
+const QUEUE_FILE: &str = "queue.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct QueuedItem {
+    rating_key: String,
+    title: String,
+    queued_at: DateTime<Utc>,
+    removal_date: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct QueueUpdate {
+    pub due: Vec<CompleteMediaItem>,
+    pub newly_queued: usize,
+    pub dropped: Vec<String>,
+    pub notification_errors: Vec<(String, Report)>,
+}
+
+/// Queues the matching items that are not queued yet, telling their requesters when they will be
+/// removed, and returns the items whose grace period is over. Items that no longer match the
+/// rules are taken out of the queue, while items that could not be gathered this time are kept.
+pub async fn update(
+    matching: Vec<CompleteMediaItem>,
+    not_matching: &[String],
+    grace_days: i64,
+) -> Result<QueueUpdate> {
+    let mut queue: Vec<QueuedItem> = state::load(QUEUE_FILE)?.unwrap_or_default();
+    let mut update = QueueUpdate::default();
+    let now = Utc::now();
+
+    queue.retain(|queued| {
+        let still_matching = !not_matching.contains(&queued.rating_key);
+        if !still_matching {
+            update.dropped.push(queued.title.clone());
+        }
+        still_matching
+    });
+
+    let removal_date = now + Duration::days(grace_days);
+    let mut notices = Vec::new();
+    for item in matching.iter() {
+        if queue
+            .iter()
+            .any(|queued| queued.rating_key == item.rating_key)
+        {
+            continue;
+        }
+
+        queue.push(QueuedItem {
+            rating_key: item.rating_key.clone(),
+            title: item.title.clone(),
+            queued_at: now,
+            removal_date,
+        });
+        notices.extend(RequesterNotice::from_item(item));
+        update.newly_queued += 1;
+    }
+
+    state::save(QUEUE_FILE, &queue)?;
+
+    // Without a grace period the items are removed right away, which the requesters hear about
+    // afterwards instead.
+    if grace_days > 0 {
+        update.notification_errors =
+            notifications::notify_requesters(notices, RemovalEvent::Upcoming { removal_date })
+                .await;
+    }
+
+    update.due = matching
+        .into_iter()
+        .filter(|item| {
+            queue
+                .iter()
+                .any(|queued| queued.rating_key == item.rating_key && queued.removal_date <= now)
+        })
+        .collect();
+
+    Ok(update)
+}
+
+pub fn remove_deleted(deleted: &[ItemSummary]) -> Result<()> {
+    let mut queue: Vec<QueuedItem> = state::load(QUEUE_FILE)?.unwrap_or_default();
+
+    queue.retain(|queued| {
+        !deleted
+            .iter()
+            .any(|item| item.rating_key == queued.rating_key)
+    });
+
+    state::save(QUEUE_FILE, &queue)
+}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Local, Utc};
+use color_eyre::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::{arr::ArrRecord, notifications::ItemSummary, overseerr::RequestRecord, state};
+
+const AUDIT_FILE: &str = "deleted_items.json";
+
+/// An item that was removed, with what is needed to add it back with the `restore` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedItem {
+    pub deleted_at: DateTime<Utc>,
+    pub item: ItemSummary,
+    /// The instances the item was removed from.
+    pub instances: Vec<ArrRecord>,
+    /// The request that was removed along with the item, if any.
+    pub request: Option<RequestRecord>,
+}
+
+impl DeletedItem {
+    /// Every deleted item that has not been restored, oldest first.
+    pub fn load_all() -> Result<Vec<Self>> {
+        Ok(state::load(AUDIT_FILE)?.unwrap_or_default())
+    }
+
+    pub fn save_all(items: &[Self]) -> Result<()> {
+        state::save(AUDIT_FILE, &items)
+    }
+
+    /// Adds the item to the end of the audit log.
+    pub fn record(self) -> Result<()> {
+        let mut items = Self::load_all()?;
+        items.push(self);
+        Self::save_all(&items)
+    }
+}
+
+impl Display for DeletedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}), deleted {} from {}",
+            self.item.title,
+            self.item.media_type,
+            self.deleted_at
+                .with_timezone(&Local)
+                .format("%d-%m-%Y %H:%M"),
+            self.instances
+                .iter()
+                .map(|instance| instance.instance_name())
+                .chain(self.request.iter().map(|_| "overseerr"))
+                .join(", ")
+        )
+    }
+}
@@ -0,0 +1,190 @@
+mod audit;
+mod plan;
+
+use color_eyre::{eyre::eyre, Report, Result};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use crate::{
+    cache,
+    media_item::CompleteMediaItem,
+    metrics,
+    notifications::{self, ItemSummary, RemovalEvent, RunMode, RunSummary},
+};
+
+pub use audit::DeletedItem;
+pub use plan::{Action, DeletionPlan};
+
+#[derive(Debug, Default)]
+pub struct DeletionResult {
+    pub deleted: Vec<ItemSummary>,
+    pub errors: Vec<(String, Report)>,
+    pub outcomes: Vec<ItemOutcome>,
+    pub notification_errors: Vec<(String, Report)>,
+}
+
+/// What happened to an item in a single service.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum StepStatus {
+    Removed,
+    Failed(String),
+    /// Left alone, as removing it would have left the item in a worse state.
+    Skipped(String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceOutcome {
+    /// The section of the service in the config, e.g. `radarr_4k`.
+    pub service: &'static str,
+    #[serde(flatten)]
+    pub status: StepStatus,
+}
+
+impl ServiceOutcome {
+    /// The outcome of removing the item from the service, counting it in the metrics if it failed.
+    pub fn new(service: &'static str, result: Result<()>) -> Self {
+        let status = match result {
+            Ok(()) => StepStatus::Removed,
+            Err(err) => {
+                metrics::record_deletion_error(service);
+                StepStatus::Failed(err.to_string())
+            }
+        };
+
+        Self { service, status }
+    }
+
+    pub fn is_removed(&self) -> bool {
+        matches!(self.status, StepStatus::Removed)
+    }
+}
+
+impl Display for ServiceOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            StepStatus::Removed => write!(f, "Removed from {}.", self.service),
+            StepStatus::Failed(ref err) => {
+                write!(f, "Failed to remove from {}: {}", self.service, err)
+            }
+            StepStatus::Skipped(ref reason) => write!(f, "Skipped {}: {}", self.service, reason),
+        }
+    }
+}
+
+/// The state an item ended up in, in every service it was removed from.
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemOutcome {
+    pub title: String,
+    pub services: Vec<ServiceOutcome>,
+}
+
+impl ItemOutcome {
+    pub fn is_removed(&self) -> bool {
+        self.services.iter().all(ServiceOutcome::is_removed)
+    }
+
+    fn to_error(&self) -> Report {
+        eyre!(self.services.iter().join(" "))
+    }
+}
+
+/// Removes the items from all services, then lets the requesters and the configured webhooks
+/// know how it went. Telling requesters about the upcoming removal is up to the caller, as
+/// that may happen long before the actual deletion.
+///
+/// The chosen items are saved as a plan before anything is removed, and it refuses to start
+/// while an earlier plan has not been finished, as that would lose track of it.
+///
+/// `on_progress` is called with the result so far after every item.
+pub async fn delete_items<F>(
+    items: Vec<CompleteMediaItem>,
+    mode: RunMode,
+    on_progress: F,
+) -> Result<DeletionResult>
+where
+    F: FnMut(&DeletionResult),
+{
+    if DeletionPlan::load()?.is_some() {
+        return Err(eyre!("An earlier deletion was interrupted before it finished. Please finish it with the resume command first."));
+    }
+
+    let plan = DeletionPlan::new(mode, &items);
+    plan.save()?;
+
+    Ok(run_plan(plan, on_progress).await)
+}
+
+/// Finishes the steps of an interrupted deletion, the same way as `delete_items`.
+pub async fn resume<F>(plan: DeletionPlan, on_progress: F) -> DeletionResult
+where
+    F: FnMut(&DeletionResult),
+{
+    run_plan(plan, on_progress).await
+}
+
+async fn run_plan<F>(mut plan: DeletionPlan, mut on_progress: F) -> DeletionResult
+where
+    F: FnMut(&DeletionResult),
+{
+    let mut result = DeletionResult::default();
+    let mut removed = Vec::new();
+
+    for index in 0..plan.items.len() {
+        plan.run_item(index).await;
+        let recorded = plan.record_item(index);
+
+        let item = &plan.items[index];
+        let outcome = item.outcome();
+        if outcome.is_removed() {
+            removed.extend(item.notice());
+            result.deleted.push(item.summary().clone());
+        } else {
+            result
+                .errors
+                .push((outcome.title.clone(), outcome.to_error()));
+        }
+        if let Err(err) = recorded {
+            result.errors.push((
+                item.summary().title.clone(),
+                eyre!(
+                    "{} It was not added to the list of deleted items, so it can not be restored.",
+                    err
+                ),
+            ));
+        }
+        result.outcomes.push(outcome);
+
+        on_progress(&result);
+    }
+
+    // Every step has been tried, and anything that failed shows up to be removed again,
+    // so the plan is done with.
+    if let Err(err) = plan.finish() {
+        result.errors.push(("the deletion plan".to_string(), err));
+    }
+
+    // The cached requests and *arr data still contain the removed items. Failing to clear it
+    // only means they show up until the cache expires, so it does not fail the deletion.
+    if !result.deleted.is_empty() {
+        let _ = cache::clear();
+    }
+
+    result
+        .notification_errors
+        .append(&mut notifications::notify_requesters(removed, RemovalEvent::Removed).await);
+
+    let summary = RunSummary::new(
+        plan.mode,
+        result.deleted.clone(),
+        &result.errors,
+        result.outcomes.clone(),
+    );
+    metrics::record_run(&summary);
+    result
+        .notification_errors
+        .append(&mut notifications::send_run_summary(&summary).await);
+
+    result
+}
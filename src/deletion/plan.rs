@@ -0,0 +1,253 @@
+use chrono::{DateTime, Utc};
+use color_eyre::{eyre::eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{audit::DeletedItem, ItemOutcome, ServiceOutcome, StepStatus};
+use crate::{
+    arr::{self, ArrRecord},
+    media_item::CompleteMediaItem,
+    notifications::{ItemSummary, RequesterNotice, RunMode},
+    overseerr::{self, RequestRecord},
+    state,
+};
+
+const PLAN_FILE: &str = "deletion_plan.json";
+
+/// A single removal from one of the services, along with what is needed to undo it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Action {
+    /// Removes the item and its files from a Sonarr or Radarr instance.
+    Arr { id: i32, record: ArrRecord },
+    /// Removes the item from Overseerr, along with its requests.
+    Request {
+        media_id: u32,
+        record: RequestRecord,
+    },
+}
+
+impl Action {
+    /// The section of the service in the config, e.g. `radarr_4k`.
+    pub fn service(&self) -> &'static str {
+        match self {
+            Self::Arr { record, .. } => record.instance_name(),
+            Self::Request { .. } => "overseerr",
+        }
+    }
+
+    async fn run(&self) -> Result<()> {
+        match self {
+            Self::Arr { id, record } => arr::remove_data(record, *id).await,
+            Self::Request { media_id, .. } => overseerr::remove_media(*media_id).await,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlannedStep {
+    action: Action,
+    /// Set right before the removal is sent, so a resumed run knows it may already have happened.
+    started: bool,
+    status: Option<StepStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PlannedRequester {
+    user_id: u32,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlannedItem {
+    item: ItemSummary,
+    requester: Option<PlannedRequester>,
+    steps: Vec<PlannedStep>,
+    /// Whether what was removed has been added to the audit log yet.
+    #[serde(default)]
+    recorded: bool,
+}
+
+impl PlannedItem {
+    fn from_item(item: &CompleteMediaItem) -> Self {
+        let requester = RequesterNotice::from_item(item).map(|notice| PlannedRequester {
+            user_id: notice.user_id(),
+            name: notice.requested_by().to_string(),
+        });
+
+        Self {
+            item: ItemSummary::from_item(item),
+            requester,
+            steps: item
+                .removal_actions()
+                .into_iter()
+                .map(|action| PlannedStep {
+                    action,
+                    started: false,
+                    status: None,
+                })
+                .collect(),
+            recorded: false,
+        }
+    }
+
+    pub fn summary(&self) -> &ItemSummary {
+        &self.item
+    }
+
+    /// The notice for the requester, once the item has been removed.
+    pub fn notice(&self) -> Option<RequesterNotice> {
+        self.requester.as_ref().map(|requester| {
+            RequesterNotice::new(requester.user_id, requester.name.clone(), self.item.clone())
+        })
+    }
+
+    /// The services the item still has to be removed from.
+    pub fn remaining_services(&self) -> Vec<&'static str> {
+        self.steps
+            .iter()
+            .filter(|step| !matches!(step.status, Some(StepStatus::Removed)))
+            .map(|step| step.action.service())
+            .collect()
+    }
+
+    pub fn outcome(&self) -> ItemOutcome {
+        ItemOutcome {
+            title: self.item.title.clone(),
+            services: self
+                .steps
+                .iter()
+                .map(|step| ServiceOutcome {
+                    service: step.action.service(),
+                    status: step
+                        .status
+                        .clone()
+                        .unwrap_or(StepStatus::Skipped("Not attempted yet.".to_string())),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The items chosen for a deletion, saved before anything is removed and updated after every
+/// step, so a deletion that was interrupted can be finished with the `resume` command.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeletionPlan {
+    pub mode: RunMode,
+    pub created_at: DateTime<Utc>,
+    pub items: Vec<PlannedItem>,
+}
+
+impl DeletionPlan {
+    pub fn new(mode: RunMode, items: &[CompleteMediaItem]) -> Self {
+        Self {
+            mode,
+            created_at: Utc::now(),
+            items: items.iter().map(PlannedItem::from_item).collect(),
+        }
+    }
+
+    /// The plan of a deletion that did not finish, if there is one.
+    pub fn load() -> Result<Option<Self>> {
+        state::load(PLAN_FILE)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::save(PLAN_FILE, self)
+    }
+
+    /// Removes the saved plan, once all of its steps have been run.
+    pub fn finish(&self) -> Result<()> {
+        state::remove(PLAN_FILE)
+    }
+
+    /// Runs every step of the item that has not succeeded yet, saving the progress after each.
+    /// The files are removed first, and the request only once they are gone from every
+    /// instance, so an item that could not be removed fully still shows up to try again.
+    pub async fn run_item(&mut self, index: usize) {
+        for step in 0..self.items[index].steps.len() {
+            let item = &mut self.items[index];
+            if matches!(item.steps[step].status, Some(StepStatus::Removed)) {
+                continue;
+            }
+
+            let action = item.steps[step].action.clone();
+            let files_removed = item.steps.iter().all(|step| match step.action {
+                Action::Arr { .. } => matches!(step.status, Some(StepStatus::Removed)),
+                Action::Request { .. } => true,
+            });
+            if let Action::Request { .. } = action {
+                if !files_removed {
+                    item.steps[step].status = Some(StepStatus::Skipped(
+                        "Kept the request, as the files were not removed everywhere.".to_string(),
+                    ));
+                    self.save_progress();
+                    continue;
+                }
+            }
+
+            let interrupted = item.steps[step].started && item.steps[step].status.is_none();
+            item.steps[step].started = true;
+            item.steps[step].status = None;
+            self.save_progress();
+
+            let result = action.run().await.map_err(|err| {
+                if interrupted {
+                    eyre!("{} It was being removed when the deletion was interrupted, so it may be gone already.", err)
+                } else {
+                    err
+                }
+            });
+
+            self.items[index].steps[step].status =
+                Some(ServiceOutcome::new(action.service(), result).status);
+            self.save_progress();
+        }
+    }
+
+    /// Adds what was removed of the item to the audit log, so it can be restored later.
+    /// An item is only recorded once, even when its deletion is resumed.
+    pub fn record_item(&mut self, index: usize) -> Result<()> {
+        let item = &self.items[index];
+        if item.recorded {
+            return Ok(());
+        }
+
+        let removed = item
+            .steps
+            .iter()
+            .filter(|step| matches!(step.status, Some(StepStatus::Removed)));
+        let instances: Vec<_> = removed
+            .clone()
+            .filter_map(|step| match step.action {
+                Action::Arr { ref record, .. } => Some(record.clone()),
+                Action::Request { .. } => None,
+            })
+            .collect();
+        let request = removed
+            .filter_map(|step| match step.action {
+                Action::Request { ref record, .. } => Some(record.clone()),
+                Action::Arr { .. } => None,
+            })
+            .next();
+
+        if !instances.is_empty() || request.is_some() {
+            DeletedItem {
+                deleted_at: Utc::now(),
+                item: item.item.clone(),
+                instances,
+                request,
+            }
+            .record()?;
+        }
+
+        self.items[index].recorded = true;
+        self.save_progress();
+        Ok(())
+    }
+
+    /// Failing to save the progress only means a resumed deletion repeats a step,
+    /// so it does not stop the deletion.
+    fn save_progress(&self) {
+        let _ = self.save();
+    }
+}
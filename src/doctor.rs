@@ -0,0 +1,329 @@
+use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Report, Result};
+use itertools::Itertools;
+use std::sync::Arc;
+
+use crate::{
+    arguments::Arguments,
+    arr, cache,
+    config::{Config, Radarr, Sonarr},
+    error::ServiceError,
+    overseerr::{self, ArrServer},
+    plex,
+    shared::MediaType,
+    tautulli,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Failed,
+}
+
+impl Status {
+    /// The status padded to the same width, as the colours would throw off the alignment.
+    fn colored(&self) -> String {
+        match self {
+            Self::Ok => format!("{:7}", "ok").green().to_string(),
+            Self::Warning => format!("{:7}", "warning").yellow().to_string(),
+            Self::Failed => format!("{:7}", "failed").red().to_string(),
+        }
+    }
+}
+
+/// The outcome of one check, with the error behind it when it failed.
+struct Check {
+    service: &'static str,
+    status: Status,
+    message: String,
+    error: Option<Report>,
+}
+
+impl Check {
+    fn ok(service: &'static str, message: impl Into<String>) -> Self {
+        Self::new(service, Status::Ok, message.into())
+    }
+
+    fn warning(service: &'static str, message: impl Into<String>) -> Self {
+        Self::new(service, Status::Warning, message.into())
+    }
+
+    fn failed(service: &'static str, message: impl Into<String>) -> Self {
+        Self::new(service, Status::Failed, message.into())
+    }
+
+    fn error(service: &'static str, err: Report) -> Self {
+        Self {
+            service,
+            status: Status::Failed,
+            message: format!("{:#}", err),
+            error: Some(err),
+        }
+    }
+
+    fn new(service: &'static str, status: Status, message: String) -> Self {
+        Self {
+            service,
+            status,
+            message,
+            error: None,
+        }
+    }
+}
+
+/// A Radarr or Sonarr instance from the config.
+struct Instance {
+    /// The name of its section in the config, e.g. `radarr_4k`.
+    section: &'static str,
+    /// The name it is shown with, e.g. `Radarr 4K`.
+    service: &'static str,
+    media_type: MediaType,
+    is_4k: bool,
+    api_key: String,
+}
+
+impl Instance {
+    fn configured(config: &Config) -> Vec<Self> {
+        let radarr = |section, service, is_4k, radarr: &Option<Radarr>| {
+            radarr.as_ref().map(|radarr| Self {
+                section,
+                service,
+                media_type: MediaType::Movie,
+                is_4k,
+                api_key: radarr.api_key.clone(),
+            })
+        };
+        let sonarr = |section, service, is_4k, sonarr: &Option<Sonarr>| {
+            sonarr.as_ref().map(|sonarr| Self {
+                section,
+                service,
+                media_type: MediaType::Tv,
+                is_4k,
+                api_key: sonarr.api_key.clone(),
+            })
+        };
+
+        [
+            radarr("radarr", "Radarr", false, &config.radarr),
+            radarr("radarr_4k", "Radarr 4K", true, &config.radarr_4k),
+            sonarr("sonarr", "Sonarr", false, &config.sonarr),
+            sonarr("sonarr_4k", "Sonarr 4K", true, &config.sonarr_4k),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+/// Contacts every service in the config, checking that it can be reached, that its key is
+/// accepted and that Overseerr knows the same Radarr and Sonarr servers.
+pub async fn run() -> Result<()> {
+    // The point is to talk to the services, a cached response says nothing about them now.
+    cache::bypass();
+
+    let mut checks = Vec::new();
+    if Config::global().needs_discovery() {
+        checks.push(discover_servers().await);
+    }
+
+    let config = Config::global();
+    let instances = Instance::configured(&config);
+
+    checks.extend(check_overseerr(&instances).await);
+    checks.push(check_plex().await);
+    checks.push(check_tautulli().await);
+    for instance in instances.iter() {
+        checks.push(
+            match arr::get_version(instance.media_type, instance.is_4k).await {
+                Ok(version) => Check::ok(instance.service, format!("Version {}", version)),
+                Err(err) => Check::error(instance.service, err),
+            },
+        );
+    }
+
+    let width = checks
+        .iter()
+        .map(|check| check.service.len())
+        .max()
+        .unwrap_or_default();
+    for check in checks.iter() {
+        println!(
+            "{:width$}  {}  {}",
+            check.service,
+            check.status.colored(),
+            check.message,
+            width = width
+        );
+    }
+
+    let warnings = count(&checks, Status::Warning);
+    let failed = count(&checks, Status::Failed);
+    if failed == 0 {
+        match warnings {
+            0 => println!("\nEverything looks good."),
+            _ => println!(
+                "\nEverything can be reached, but {} checks gave a warning.",
+                warnings
+            ),
+        }
+        return Ok(());
+    }
+
+    let message = format!("{} of {} checks failed", failed, checks.len());
+    let first_error = checks.into_iter().find_map(|check| {
+        check
+            .error
+            .filter(|err| err.chain().any(|cause| cause.is::<ServiceError>()))
+    });
+
+    match first_error {
+        // Keep the request error behind it, so the exit code tells what kind of problem it was.
+        Some(err) => Err(err.wrap_err(message)),
+        None => Err(eyre!(message)),
+    }
+}
+
+fn count(checks: &[Check], status: Status) -> usize {
+    checks.iter().filter(|check| check.status == status).count()
+}
+
+/// Looks up the Radarr and Sonarr servers in Overseerr, the same way the other commands do.
+/// When that fails, only the servers in the config are checked.
+async fn discover_servers() -> Check {
+    let config = match Config::load(Arguments::get_args().config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => return Check::error("Overseerr", err),
+    };
+
+    match config.discover_servers().await {
+        Ok(config) => {
+            let found = Instance::configured(&config)
+                .into_iter()
+                .map(|instance| instance.service)
+                .join(", ");
+            Config::set_global(Arc::new(config));
+            Check::ok(
+                "Overseerr",
+                format!("Looked up the Radarr and Sonarr servers, using {}", found),
+            )
+        }
+        Err(err) => Check::error("Overseerr", err),
+    }
+}
+
+async fn check_overseerr(instances: &[Instance]) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    match overseerr::get_version().await {
+        Ok(version) => checks.push(Check::ok("Overseerr", format!("Version {}", version))),
+        Err(err) => return vec![Check::error("Overseerr", err)],
+    }
+
+    // Unlike the status, the settings need a valid API key.
+    match overseerr::csrf_protection().await {
+        Ok(false) => checks.push(Check::ok("Overseerr", "CSRF protection is off")),
+        Ok(true) => checks.push(Check::warning(
+            "Overseerr",
+            "CSRF protection is on, which makes Overseerr refuse removals. Turn it off under Settings > General.",
+        )),
+        Err(err) => {
+            checks.push(Check::error("Overseerr", err));
+            return checks;
+        }
+    }
+
+    for media_type in [MediaType::Movie, MediaType::Tv] {
+        match overseerr::get_arr_servers(&Config::global().seerr, media_type).await {
+            Ok(servers) => checks.extend(compare_servers(media_type, instances, &servers)),
+            Err(err) => checks.push(Check::error("Overseerr", err)),
+        }
+    }
+
+    checks
+}
+
+/// Matches the instances in the config to the servers in Overseerr by their API key, as their
+/// URLs can differ between what Overseerr and this program use to reach them.
+fn compare_servers(
+    media_type: MediaType,
+    instances: &[Instance],
+    servers: &[ArrServer],
+) -> Vec<Check> {
+    let instances = instances
+        .iter()
+        .filter(|instance| instance.media_type == media_type)
+        .collect::<Vec<_>>();
+    let mut checks = Vec::new();
+
+    for instance in instances.iter() {
+        let check = match servers
+            .iter()
+            .find(|server| server.api_key == instance.api_key)
+        {
+            Some(server) if server.is_4k == instance.is_4k => Check::ok(
+                instance.service,
+                format!("Matches \"{}\" in Overseerr", server.name),
+            ),
+            Some(server) => Check::failed(
+                instance.service,
+                format!(
+                    "Is set up as {} here, but \"{}\" is {} server in Overseerr",
+                    instance.section,
+                    server.name,
+                    if server.is_4k { "a 4K" } else { "a standard" }
+                ),
+            ),
+            None => Check::warning(
+                instance.service,
+                "Is not set up in Overseerr, its items will never have a request",
+            ),
+        };
+        checks.push(check);
+    }
+
+    for server in servers.iter() {
+        if !instances
+            .iter()
+            .any(|instance| instance.api_key == server.api_key)
+        {
+            checks.push(Check::warning(
+                "Overseerr",
+                format!(
+                    "\"{}\" at {} is not in the config, its requests will not be cleaned up",
+                    server.name, server.url
+                ),
+            ));
+        }
+    }
+
+    checks
+}
+
+async fn check_plex() -> Check {
+    let version = match plex::get_version().await {
+        Ok(version) => version,
+        Err(err) => return Check::error("Plex", err),
+    };
+
+    // The identity can be read without a token, the libraries can't.
+    match plex::count_libraries().await {
+        Ok(libraries) => Check::ok(
+            "Plex",
+            format!(
+                "Version {}, with {} movie and show libraries",
+                version, libraries
+            ),
+        ),
+        Err(err) => Check::error("Plex", err),
+    }
+}
+
+async fn check_tautulli() -> Check {
+    match tautulli::get_server_info().await {
+        Ok((name, version)) => Check::ok(
+            "Tautulli",
+            format!("Connected to {} running Plex {}", name, version),
+        ),
+        Err(err) => Check::error("Tautulli", err),
+    }
+}
@@ -0,0 +1,253 @@
+use color_eyre::Report;
+use itertools::Itertools;
+use reqwest::{Response, StatusCode};
+use std::fmt::Display;
+
+use crate::config::Config;
+
+/// The most of a response body shown in an error, in characters.
+const BODY_SNIPPET_LENGTH: usize = 200;
+
+/// The broad cause of a failed request, used to explain it and to group similar errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The API key or token was refused.
+    Auth,
+    /// Nothing was found at the endpoint, usually because the URL is wrong.
+    NotFound,
+    /// The request was refused as invalid, usually because the API has changed.
+    BadRequest,
+    /// The service is down or overloaded.
+    Unavailable,
+    /// Any other failed status.
+    Status,
+    /// The response could not be read.
+    Parse,
+    /// The service could not be reached.
+    Network,
+    /// The service did not respond in time.
+    Timeout,
+}
+
+impl ErrorKind {
+    fn from_status(status: StatusCode) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Self::Auth,
+            StatusCode::NOT_FOUND => Self::NotFound,
+            StatusCode::BAD_REQUEST => Self::BadRequest,
+            StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => Self::Unavailable,
+            _ => Self::Status,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Auth => "the API key was refused",
+            Self::NotFound => "nothing was found at the URL",
+            Self::BadRequest => "the request was refused",
+            Self::Unavailable => "the service is down or overloaded",
+            Self::Status => "the request failed",
+            Self::Parse => "the response could not be read",
+            Self::Network => "the service could not be reached",
+            Self::Timeout => "the service did not respond in time",
+        }
+    }
+
+    /// The exit code of a command that failed with this kind of error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Auth => 3,
+            Self::NotFound | Self::BadRequest | Self::Status | Self::Parse => 4,
+            Self::Unavailable | Self::Network | Self::Timeout => 5,
+        }
+    }
+}
+
+/// A request to one of the services that failed.
+#[derive(Debug)]
+pub struct ServiceError {
+    /// The name of the service, e.g. `Radarr`.
+    pub service: &'static str,
+    /// The path that was requested, without the query, as that can hold API keys.
+    pub endpoint: String,
+    pub kind: ErrorKind,
+    pub status: Option<StatusCode>,
+    /// The start of the response body, which often explains what went wrong.
+    pub body: Option<String>,
+    /// The error behind it, when there was no usable response.
+    pub cause: Option<String>,
+    /// How many times the request was sent.
+    pub attempts: u32,
+}
+
+impl ServiceError {
+    /// The error for a response with a failed status.
+    pub async fn from_response(service: &'static str, endpoint: &str, response: Response) -> Self {
+        let status = response.status();
+        let body = response
+            .text()
+            .await
+            .ok()
+            .map(|body| snippet(&body))
+            .filter(|body| !body.is_empty());
+
+        Self {
+            service,
+            endpoint: endpoint.to_string(),
+            kind: ErrorKind::from_status(status),
+            status: Some(status),
+            body,
+            cause: None,
+            attempts: 1,
+        }
+    }
+
+    /// The error for a response that could not be read into the expected type.
+    pub fn parse(service: &'static str, endpoint: &str, err: impl Display, body: &str) -> Self {
+        Self {
+            service,
+            endpoint: endpoint.to_string(),
+            kind: ErrorKind::Parse,
+            status: None,
+            body: Some(snippet(body)),
+            cause: Some(redact(&err.to_string())),
+            attempts: 1,
+        }
+    }
+
+    /// The error for a request that got no response at all.
+    pub fn request(
+        service: &'static str,
+        endpoint: &str,
+        kind: ErrorKind,
+        cause: String,
+        attempts: u32,
+    ) -> Self {
+        Self {
+            service,
+            endpoint: endpoint.to_string(),
+            kind,
+            status: None,
+            body: None,
+            cause: Some(redact(&cause)),
+            attempts,
+        }
+    }
+}
+
+impl std::error::Error for ServiceError {}
+
+impl Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = self
+            .status
+            .map(|status| status.to_string())
+            .unwrap_or_default();
+        let cause = self.cause.as_deref().unwrap_or_default();
+
+        match self.kind {
+            ErrorKind::Auth => write!(
+                f,
+                "Got {} from {}, please check the appropriate API key.",
+                status, self.service
+            )?,
+            ErrorKind::NotFound => write!(
+                f,
+                "Got {} from {} at path {}. Please make sure the URL is correct.",
+                status, self.service, self.endpoint
+            )?,
+            ErrorKind::BadRequest => write!(
+                f,
+                "Got {} from {} at {}. The api may have changed, please report this on Github.",
+                status, self.service, self.endpoint
+            )?,
+            ErrorKind::Unavailable => write!(
+                f,
+                "Got {} from {}, it may be down or overloaded. Please try again later.",
+                status, self.service
+            )?,
+            ErrorKind::Status => write!(
+                f,
+                "Got {} from {} at {}.",
+                status, self.service, self.endpoint
+            )?,
+            ErrorKind::Parse => write!(
+                f,
+                "Could not read the response from {} at {}, the api may have changed: {}.",
+                self.service, self.endpoint, cause
+            )?,
+            ErrorKind::Network => write!(
+                f,
+                "{} could not be reached at {}: {}.",
+                self.service, self.endpoint, cause
+            )?,
+            ErrorKind::Timeout => write!(
+                f,
+                "{} did not respond at {} {}.",
+                self.service, self.endpoint, cause
+            )?,
+        }
+
+        if self.attempts > 1 {
+            write!(f, " Gave up after {} attempts.", self.attempts)?;
+        }
+        if let Some(ref body) = self.body {
+            write!(f, " The response was: {}", body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The start of a response body, with any secret left out. Services can echo a refused key.
+fn snippet(body: &str) -> String {
+    redact(body.trim())
+        .chars()
+        .take(BODY_SNIPPET_LENGTH)
+        .collect()
+}
+
+/// Replaces every API key, token and password from the config in the text.
+pub fn redact(text: &str) -> String {
+    let config = match Config::try_global() {
+        Some(config) => config,
+        None => return text.to_string(),
+    };
+
+    config
+        .secrets()
+        .into_iter()
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret, "[redacted]")
+        })
+}
+
+/// Counts the errors per service and cause, most common first, e.g. ("Radarr: the API key was
+/// refused", 3). Errors that did not come from a request to a service are counted together.
+pub fn group(errors: &[Report]) -> Vec<(String, usize)> {
+    errors
+        .iter()
+        .map(|err| match find(err) {
+            Some(err) => format!("{}: {}", err.service, err.kind.description()),
+            None => "Other errors".to_string(),
+        })
+        .counts()
+        .into_iter()
+        .sorted_by(|(name1, count1), (name2, count2)| count2.cmp(count1).then(name1.cmp(name2)))
+        .collect()
+}
+
+/// The exit code for a command that failed with the error.
+/// Failed requests get a code for their kind, anything else exits with 1.
+pub fn exit_code(err: &Report) -> i32 {
+    find(err).map_or(1, |err| err.kind.exit_code())
+}
+
+/// The service error behind the report, if it was caused by one.
+fn find(err: &Report) -> Option<&ServiceError> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ServiceError>())
+}
@@ -0,0 +1,151 @@
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use color_eyre::Result;
+use serde::Serialize;
+use std::fs;
+
+use crate::{
+    arguments::Arguments, gather, media_item::CompleteMediaItem, shared::MediaType,
+    utils::human_file_size,
+};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+/// An item as it is exported, with what is needed to decide whether to remove it.
+#[derive(Debug, Serialize)]
+struct ExportItem {
+    rating_key: String,
+    title: String,
+    media_type: MediaType,
+    size: i64,
+    requested_by: Option<String>,
+    requested_at: Option<DateTime<Utc>>,
+    last_watched: Option<DateTime<Utc>>,
+    watched_by_requester: bool,
+}
+
+impl ExportItem {
+    fn from_item(item: &CompleteMediaItem) -> Self {
+        Self {
+            rating_key: item.rating_key.clone(),
+            title: item.title.clone(),
+            media_type: item.media_type,
+            size: item.get_disk_size(),
+            requested_by: item
+                .get_request()
+                .map(|request| request.requested_by.clone()),
+            requested_at: item.get_requested_date(),
+            last_watched: item.get_last_watched(),
+            watched_by_requester: item.is_watched_by_requester(),
+        }
+    }
+
+    fn csv_row(&self) -> String {
+        let date = |date: Option<DateTime<Utc>>| date.map(|date| date.to_rfc3339());
+
+        [
+            self.rating_key.clone(),
+            csv_field(&self.title),
+            self.media_type.to_string(),
+            self.size.to_string(),
+            csv_field(self.requested_by.as_deref().unwrap_or_default()),
+            date(self.requested_at).unwrap_or_default(),
+            date(self.last_watched).unwrap_or_default(),
+            self.watched_by_requester.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+const CSV_HEADER: &str =
+    "rating_key,title,media_type,size,requested_by,requested_at,last_watched,watched_by_requester";
+
+/// Quotes the field if it contains anything that would break up the row.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Gathers the items in the order from the arguments, leaving out the ones that failed.
+async fn gather_sorted() -> Result<Vec<CompleteMediaItem>> {
+    let args = Arguments::get_args();
+
+    eprintln!("Gathering all required data from your services.\nDepending on the amount of data and your connection speed, this could take a while...");
+    let (mut items, errors) = gather::gather_items(args.all_media).await?;
+
+    if !errors.is_empty() {
+        eprintln!(
+            "Left out {} items that could not be gathered:",
+            errors.len()
+        );
+        errors.iter().for_each(|err| eprintln!("- {}", err));
+    }
+
+    gather::sort_items(&mut items, &args.sorting.clone().unwrap_or_default());
+    Ok(items)
+}
+
+/// Prints a line for every item, without asking anything.
+pub async fn list() -> Result<()> {
+    let items = gather_sorted().await?;
+
+    for item in items.iter() {
+        let last_watched = match item.get_last_watched() {
+            Some(date) => date.with_timezone(&Local).format("%d-%m-%Y").to_string(),
+            None => "never".to_string(),
+        };
+        let requested_by = match item.get_request() {
+            Some(request) => format!(", requested by {}", request.requested_by),
+            None => String::new(),
+        };
+
+        println!(
+            "{} ({}, {}) - last watched {}{}",
+            item.title,
+            item.media_type,
+            human_file_size(item.get_disk_size()),
+            last_watched,
+            requested_by
+        );
+    }
+
+    Ok(())
+}
+
+/// Writes the items to the chosen file, or to stdout.
+pub async fn export() -> Result<()> {
+    let options = &Arguments::get_args().export;
+    let items = gather_sorted()
+        .await?
+        .iter()
+        .map(ExportItem::from_item)
+        .collect::<Vec<_>>();
+
+    let rendered = match options.format {
+        ExportFormat::Json => serde_json::to_string_pretty(&items)? + "\n",
+        ExportFormat::Csv => {
+            let rows = items.iter().map(ExportItem::csv_row);
+            std::iter::once(CSV_HEADER.to_string())
+                .chain(rows)
+                .map(|row| row + "\n")
+                .collect()
+        }
+    };
+
+    match options.output {
+        Some(ref path) => {
+            fs::write(path, rendered)?;
+            eprintln!("Exported {} items to {}.", items.len(), path);
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
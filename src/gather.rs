@@ -0,0 +1,161 @@
+use color_eyre::{eyre::eyre, Report, Result};
+use futures::future::join_all;
+use itertools::Itertools;
+use std::{borrow::Borrow, collections::HashMap};
+use tokio::try_join;
+
+use crate::{
+    arr::ArrData,
+    config::Config,
+    media_item::{CompleteMediaItem, MediaItem},
+    metrics,
+    overseerr::{MediaRequest, ServerItem},
+    plex, policy, requesters,
+    shared::{MediaType, Order, SortingOption, SortingValue},
+    tautulli::{self, LibraryHistory},
+    users::UserRegistry,
+};
+
+/// Everything the media items are joined with, fetched in bulk from each service.
+#[derive(Debug)]
+pub struct Library {
+    pub titles: HashMap<String, String>,
+    pub history: LibraryHistory,
+    movies: HashMap<i32, ArrData>,
+    movies_4k: HashMap<i32, ArrData>,
+    series: HashMap<i32, ArrData>,
+    series_4k: HashMap<i32, ArrData>,
+}
+
+impl Library {
+    async fn fetch() -> Result<Self> {
+        let (titles, history, movies, movies_4k, series, series_4k) = try_join!(
+            plex::get_titles(),
+            tautulli::get_library_history(),
+            ArrData::get_all(MediaType::Movie, false),
+            ArrData::get_all(MediaType::Movie, true),
+            ArrData::get_all(MediaType::Tv, false),
+            ArrData::get_all(MediaType::Tv, true),
+        )?;
+
+        Ok(Self {
+            titles,
+            history,
+            movies,
+            movies_4k,
+            series,
+            series_4k,
+        })
+    }
+
+    pub fn arr_data(&self, media_type: MediaType, is_4k: bool, id: i32) -> Result<ArrData> {
+        let (data, service) = match (media_type, is_4k) {
+            (MediaType::Movie, false) => (&self.movies, "Radarr"),
+            (MediaType::Movie, true) => (&self.movies_4k, "Radarr 4K"),
+            (MediaType::Tv, false) => (&self.series, "Sonarr"),
+            (MediaType::Tv, true) => (&self.series_4k, "Sonarr 4K"),
+        };
+
+        data.get(&id).cloned().ok_or_else(|| {
+            eyre!(
+                "{} has no item with id {}. Unable to gather file data.",
+                service,
+                id
+            )
+        })
+    }
+}
+
+/// Gathers every available request, or with `all_items` every available item on the server,
+/// together with its data from the other services. Items that could not be completed are
+/// returned as errors instead of failing the whole gathering.
+pub async fn gather_items(all_items: bool) -> Result<(Vec<CompleteMediaItem>, Vec<Report>)> {
+    UserRegistry::load().await;
+
+    let mut media_items = MediaRequest::get_all()
+        .await?
+        .into_iter()
+        .map(MediaItem::from_request)
+        .collect_vec();
+
+    // This is done by merging the two lists, because Overseerr does not send who requested the media along
+    // when getting all of the media on the server. Neither does Overseerr have an endpoint for getting all
+    // requests associated with an item.
+    //
+    // If that was allowed, this could be made much nicer and more performance friendly.
+    if all_items {
+        let mut not_requested_media_items = ServerItem::get_all()
+            .await?
+            .into_iter()
+            .map(MediaItem::from_server_item)
+            .collect_vec();
+
+        media_items.append(&mut not_requested_media_items);
+
+        media_items.sort_by(|item1, item2| {
+            (&item1.rating_key, item1.request.is_none())
+                .cmp(&(&item2.rating_key, item2.request.is_none()))
+        });
+        media_items.dedup_by(|item1, item2| item1.rating_key == item2.rating_key);
+    }
+
+    let media_items = media_items
+        .into_iter()
+        .filter(|i| i.is_available() && i.has_manager_active() && !i.user_ignored())
+        .collect_vec();
+    let library = Library::fetch().await?;
+
+    let mut errors: Vec<Report> = Vec::new();
+
+    let mut complete_items = media_items
+        .into_iter()
+        .filter_map(|item| match item.into_complete_media(&library) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        })
+        .unique_by(|item| item.title.clone())
+        .sorted_by(|item1, item2| item1.title.cmp(&item2.title))
+        .collect_vec();
+
+    // Sonarr only lists the episodes of one series at a time, so they are only looked up for the
+    // requested series, and only when the policy or the quotas need to know who watched what.
+    let config = Config::global();
+    if config.requester_policy.is_some() || config.requester_quota.is_some() {
+        join_all(
+            complete_items
+                .iter_mut()
+                .map(|item| item.find_last_available_episode()),
+        )
+        .await;
+    }
+
+    // Before anything is held back, as the held back requests still take up space.
+    requesters::record_usage(&mut complete_items);
+    let complete_items = policy::hold_back_unfulfilled(complete_items);
+    metrics::record_candidates(&complete_items);
+
+    Ok((complete_items, errors))
+}
+
+/// Sorts gathered items the way the user chose. Unwatched requests from requesters over their
+/// quota come first, followed by fulfilled requests when there is a requester policy.
+/// Relies on the items still being sorted by title, as `gather_items` returns them.
+pub fn sort_items<T: Borrow<CompleteMediaItem>>(items: &mut [T], sort: &SortingOption) {
+    match sort.sorting_value {
+        SortingValue::Name => (),
+        SortingValue::Size => items.sort_by_key(|item| item.borrow().get_disk_size()),
+        SortingValue::Type => items.sort_by_key(|item| item.borrow().media_type),
+        SortingValue::RequestedDate => items.sort_by_key(|item| item.borrow().get_requested_date()),
+    }
+
+    // Reverse if sorting direction is descending
+    if sort.sorting_direction == Order::Desc {
+        items.reverse();
+    }
+
+    policy::prioritise_fulfilled(items);
+    policy::prioritise_over_quota(items);
+}
@@ -0,0 +1,233 @@
+use color_eyre::Result;
+use once_cell::sync::Lazy;
+use reqwest::{header::RETRY_AFTER, Client, Method, Request, RequestBuilder, Response, StatusCode};
+use std::{
+    collections::HashMap,
+    error::Error,
+    iter,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{Mutex as AsyncMutex, Semaphore},
+    time::Instant,
+};
+
+use crate::{
+    config::RequestSettings,
+    error::{ErrorKind, ServiceError},
+    metrics,
+};
+
+static CLIENTS: Lazy<Mutex<HashMap<&'static str, Arc<ServiceClient>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The wait before the first retry, doubled for every retry after it.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// The longest wait between two attempts, including waits asked for through `Retry-After`.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A client shared by every request to one instance of a service, so connections are reused
+/// and its limits apply to the whole program instead of to a single call.
+pub struct ServiceClient {
+    service: &'static str,
+    client: Client,
+    settings: RequestSettings,
+    permits: Semaphore,
+    next_slot: AsyncMutex<Instant>,
+}
+
+/// What to do with the outcome of a single attempt.
+enum Outcome {
+    Done(Response),
+    Retry(Option<Duration>),
+    Fatal(reqwest::Error),
+}
+
+impl Outcome {
+    /// Sorts the outcome into done, worth another try, or fatal.
+    /// Timeouts, connection failures and statuses that point to a temporary problem on the
+    /// service's side are retried. Other failed statuses are left to the caller to report.
+    fn classify(result: reqwest::Result<Response>) -> Self {
+        match result {
+            Ok(response) => match response.status() {
+                StatusCode::REQUEST_TIMEOUT
+                | StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT => Self::Retry(retry_after(&response)),
+                _ => Self::Done(response),
+            },
+            Err(err) if err.is_timeout() || err.is_connect() => Self::Retry(None),
+            Err(err) => Self::Fatal(err),
+        }
+    }
+}
+
+impl ServiceClient {
+    fn new(service: &'static str, settings: &RequestSettings) -> Self {
+        Self {
+            service,
+            client: Client::new(),
+            settings: settings.clone(),
+            permits: Semaphore::new(settings.max_concurrent_requests),
+            next_slot: AsyncMutex::new(Instant::now()),
+        }
+    }
+
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.client.post(url)
+    }
+
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.client.delete(url)
+    }
+
+    /// Sends the request as soon as the concurrency and rate limits of the service allow it.
+    ///
+    /// GET requests are retried with an exponential backoff when they fail in a way that is
+    /// likely temporary. Anything else is only sent once, as it may not be safe to repeat.
+    /// If every attempt gets a failed status, the last response is returned so the caller
+    /// can report it like any other status.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let request = request
+            .timeout(Duration::from_secs(self.settings.timeout_seconds))
+            .build()
+            .map_err(|err| err.without_url())?;
+        let endpoint = request.url().path().to_string();
+        let max_retries = match *request.method() {
+            Method::GET => self.settings.max_retries,
+            _ => 0,
+        };
+
+        let mut attempt = 0;
+        loop {
+            let retry = match request.try_clone() {
+                Some(retry) if attempt < max_retries => retry,
+                _ => {
+                    return self
+                        .send_once(request)
+                        .await
+                        .map_err(|err| self.error(&endpoint, err, attempt).into())
+                }
+            };
+
+            let wait = match Outcome::classify(self.send_once(retry).await) {
+                Outcome::Done(response) => return Ok(response),
+                Outcome::Fatal(err) => return Err(self.error(&endpoint, err, attempt).into()),
+                Outcome::Retry(wait) => {
+                    wait.unwrap_or(INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(attempt)))
+                }
+            };
+
+            tokio::time::sleep(wait.min(MAX_BACKOFF)).await;
+            attempt += 1;
+        }
+    }
+
+    /// Reads the body of a response, failing the same way as `send` if the connection breaks.
+    pub async fn text(&self, endpoint: &str, response: Response) -> Result<String> {
+        response
+            .text()
+            .await
+            .map_err(|err| self.error(endpoint, err, 0).into())
+    }
+
+    async fn send_once(&self, request: Request) -> reqwest::Result<Response> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("The request semaphore is never closed.");
+        self.wait_for_slot().await;
+
+        metrics::time_request(self.service, self.client.execute(request)).await
+    }
+
+    /// Waits until the next request is allowed by `requests_per_second`, if it is set.
+    async fn wait_for_slot(&self) {
+        let interval = match self.settings.requests_per_second {
+            Some(per_second) => Duration::from_secs_f64(1.0 / per_second),
+            None => return,
+        };
+
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+
+    fn error(&self, endpoint: &str, err: reqwest::Error, retries: u32) -> ServiceError {
+        let (kind, cause) = if err.is_timeout() {
+            (
+                ErrorKind::Timeout,
+                format!("within {} seconds", self.settings.timeout_seconds),
+            )
+        } else {
+            (ErrorKind::Network, root_cause(err))
+        };
+
+        ServiceError::request(self.service_name(), endpoint, kind, cause, retries + 1)
+    }
+
+    fn service_name(&self) -> &'static str {
+        match self.service {
+            "overseerr" => "Overseerr",
+            "plex" => "Plex",
+            "tautulli" => "Tautulli",
+            "radarr" => "Radarr",
+            "sonarr" => "Sonarr",
+            service => service,
+        }
+    }
+}
+
+/// The innermost error says what actually went wrong, and leaves out the URL, which can hold an
+/// API key.
+pub fn root_cause(err: reqwest::Error) -> String {
+    let err = err.without_url();
+    iter::successors(Some(&err as &dyn Error), |err| (*err).source())
+        .last()
+        .map(|err| err.to_string())
+        .unwrap_or_default()
+}
+
+/// The wait asked for by the service through the `Retry-After` header, in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// The shared client for an instance, e.g. `radarr_4k` of the `radarr` service.
+/// A new one is created when its settings in the config have changed since the last request.
+pub fn client(
+    service: &'static str,
+    instance: &'static str,
+    settings: &RequestSettings,
+) -> Arc<ServiceClient> {
+    let mut clients = CLIENTS.lock().expect("Client lock has been poisoned.");
+
+    match clients.get(instance) {
+        Some(client) if client.settings == *settings => client.clone(),
+        _ => {
+            let client = Arc::new(ServiceClient::new(service, settings));
+            clients.insert(instance, client.clone());
+            client
+        }
+    }
+}
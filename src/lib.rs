@@ -0,0 +1,43 @@
+//! Finds media on a Plex server that nobody watches anymore and removes it from Overseerr,
+//! Radarr and Sonarr.
+//!
+//! The `media-cleaner` binary is a thin wrapper around [`cli::run`]. Other programs can use
+//! [`MediaCleaner`] to gather and delete items with a config of their own.
+
+mod arguments;
+mod arr;
+mod cache;
+mod cleaner;
+pub mod cli;
+mod config;
+mod daemon;
+mod deletion;
+mod doctor;
+mod error;
+mod export;
+mod gather;
+mod http;
+mod media_item;
+mod metrics;
+mod notifications;
+mod overseerr;
+mod plex;
+mod policy;
+mod report;
+mod requesters;
+mod restore;
+mod rules;
+mod server;
+mod shared;
+mod state;
+mod tautulli;
+mod users;
+mod utils;
+
+pub use cleaner::MediaCleaner;
+pub use config::Config;
+pub use deletion::{DeletionResult, ItemOutcome, ServiceOutcome, StepStatus};
+pub use error::{exit_code, ErrorKind, ServiceError};
+pub use media_item::CompleteMediaItem;
+pub use notifications::{ItemSummary, RunMode};
+pub use shared::MediaType;
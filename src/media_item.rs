@@ -1,21 +1,20 @@
 use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Result};
 use std::fmt::{Debug, Display};
 use chrono::{DateTime, Utc};
-use tokio::try_join;
 
 use crate::{
     arr::{self, ArrData},
-    config::Config,
+    config::{default_min_progress, Config},
+    deletion::Action,
+    gather::Library,
     overseerr::{MediaRequest, MediaStatus, ServerItem},
-    plex::PlexData,
     shared::MediaType,
-    tautulli::{self, WatchHistory},
+    tautulli::WatchHistory,
     utils::human_file_size,
 };
 
 #[derive(Debug)]
 pub struct MediaItem {
-    pub title: Option<String>,
     pub rating_key: Option<String>,
     manager_id: Option<i32>,
     manager_4k_id: Option<i32>,
@@ -27,7 +26,6 @@ pub struct MediaItem {
 impl MediaItem {
     pub fn from_request(request: MediaRequest) -> Self {
         Self {
-            title: None,
             rating_key: request.rating_key.clone(),
             manager_id: request.manager_id,
             manager_4k_id: request.manager_4k_id,
@@ -39,7 +37,6 @@ impl MediaItem {
 
     pub fn from_server_item(item: ServerItem) -> Self {
         Self {
-            title: None,
             rating_key: Some(item.rating_key),
             manager_id: item.manager_id,
             manager_4k_id: item.manager_id_4k,
@@ -49,28 +46,46 @@ impl MediaItem {
         }
     }
 
-    pub async fn into_complete_media(self) -> Result<CompleteMediaItem> {
-        let metadata = self.retrieve_metadata();
-        let history = self.retrieve_history();
-        let data = self.retrieve_arr_data();
+    /// Joins the item with its data from the other services.
+    pub fn into_complete_media(self, library: &Library) -> Result<CompleteMediaItem> {
+        let rating_key = match self.rating_key {
+            Some(ref rating_key) => rating_key,
+            None => {
+                return Err(eyre!(
+                    "No rating key was found for request. Unable to gather its data."
+                ))
+            }
+        };
 
-        let (details, history, (arr_data, arr_4k_data)) = try_join!(metadata, history, data)?;
+        let title = match library.titles.get(rating_key) {
+            Some(title) => title.clone(),
+            None => {
+                return Err(eyre!(
+                    "Plex has no item with rating key {}. Unable to gather metadata from Plex.",
+                    rating_key
+                ))
+            }
+        };
+        let history = library.history.item_watches(rating_key, &self.media_type);
+        let (arr_data, arr_4k_data) = self.find_arr_data(library)?;
 
         Ok(CompleteMediaItem {
-            title: details.title.clone(),
+            title,
+            rating_key: rating_key.clone(),
             media_type: self.media_type,
             request: self.request,
             history,
             arr_data,
             arr_4k_data,
+            requester_usage: 0,
         })
     }
 
     pub fn is_available(&self) -> bool {
-        match &self.media_status {
-            MediaStatus::Available | MediaStatus::PartiallyAvailable => true,
-            _ => false,
-        }
+        matches!(
+            &self.media_status,
+            MediaStatus::Available | MediaStatus::PartiallyAvailable
+        )
     }
 
     pub fn has_manager_active(&self) -> bool {
@@ -86,98 +101,173 @@ impl MediaItem {
             Some(ref request) => request,
         };
 
-        let ignored_users = match Config::global().ignored_users {
+        let config = Config::global();
+        let ignored_users = match config.ignored_users {
             None => return false,
             Some(ref users) => users,
         };
 
-        if ignored_users.contains(&request.requested_by)
-        // .iter()
-        // .any(|user| user.eq(&request.requested_by))
-        {
-            true
-        } else {
-            false
-        }
+        ignored_users
+            .iter()
+            .any(|user| request.requester.has_name(user))
     }
 
-    async fn retrieve_history(&self) -> Result<WatchHistory> {
-        let rating_key = match self.rating_key {
-            Some(ref rating_key) => rating_key,
-            None => {
-                return Err(eyre!(
-                "No rating key was found for request. Unable to gather watch history from Tautulli."
-            ))
-            }
-        };
-
-        tautulli::get_item_watches(rating_key, &self.media_type).await
-    }
+    fn find_arr_data(&self, library: &Library) -> Result<(Option<ArrData>, Option<ArrData>)> {
+        if self.manager_id.is_none() && self.manager_4k_id.is_none() {
+            return Err(eyre!(
+                "No *arr id was found for request. Unable to gather file data."
+            ));
+        }
 
-    async fn retrieve_metadata(&self) -> Result<PlexData> {
-        let rating_key = match self.rating_key {
-            Some(ref rating_key) => rating_key,
-            None => {
-                return Err(eyre!(
-                    "No rating key was found for request. Unable to gather metadata from Plex."
-                ))
-            }
+        let arr_data = match self.manager_id {
+            Some(id) => Some(library.arr_data(self.media_type, false, id)?),
+            None => None,
+        };
+        let arr_4k_data = match self.manager_4k_id {
+            Some(id) => Some(library.arr_data(self.media_type, true, id)?),
+            None => None,
         };
 
-        PlexData::get_data(rating_key, self.media_type).await
-    }
-
-    async fn retrieve_arr_data(&self) -> Result<(Option<ArrData>, Option<ArrData>)> {
-        match (self.manager_id, self.manager_4k_id) {
-            (Some(id), Some(id_4k)) => {
-                let data_standard = ArrData::get_data(self.media_type, id);
-                let data_4k = ArrData::get_4k_data(self.media_type, id_4k);
-
-                let (data_standard, data_4k) = try_join!(data_standard, data_4k)?;
-
-                Ok((Some(data_standard), Some(data_4k)))
-            }
-            (Some(id), _) => Ok((Some(ArrData::get_data(self.media_type, id).await?), None)),
-            (None, Some(id_4k)) => Ok((
-                Some(ArrData::get_4k_data(self.media_type, id_4k).await?),
-                None,
-            )),
-            (None, None) => Err(eyre!(
-                "No *arr id was found for request. Unable to gather file data."
-            )),
-        }
+        Ok((arr_data, arr_4k_data))
     }
 }
 
 #[derive(Debug)]
 pub struct CompleteMediaItem {
     pub title: String,
+    pub rating_key: String,
     pub media_type: MediaType,
     request: Option<MediaRequest>,
     history: WatchHistory,
     arr_data: Option<ArrData>,
     arr_4k_data: Option<ArrData>,
+    /// The space taken by all gathered requests of the same requester.
+    requester_usage: i64,
 }
 
 impl CompleteMediaItem {
-    pub async fn remove_from_server(self) -> Result<()> {
-        if let Some(request) = self.request {
-            request.remove_request().await?;
+    /// The removals needed to take the item off the server, with the files before the request.
+    pub fn removal_actions(&self) -> Vec<Action> {
+        let arr_actions = [&self.arr_data, &self.arr_4k_data]
+            .into_iter()
+            .flatten()
+            .map(|arr_data| Action::Arr {
+                id: arr_data.id(),
+                record: arr_data.record().clone(),
+            });
+        let request_action = self.request.iter().map(|request| Action::Request {
+            media_id: request.media_id,
+            record: request.record(),
+        });
+
+        arr_actions.chain(request_action).collect()
+    }
+
+    pub fn get_request(&self) -> Option<&MediaRequest> {
+        self.request.as_ref()
+    }
+
+    pub fn get_requested_date(&self) -> Option<DateTime<Utc>> {
+        self.request.as_ref().map(|request| request.created_at)
+    }
+
+    pub fn get_history(&self) -> &WatchHistory {
+        &self.history
+    }
+
+    pub fn get_arr_data(&self) -> Option<&ArrData> {
+        self.arr_data.as_ref().or(self.arr_4k_data.as_ref())
+    }
+
+    /// The size on disk in each *arr instance the item is in.
+    pub fn get_instance_sizes(&self) -> Vec<(&'static str, i64)> {
+        let standard = self
+            .arr_data
+            .iter()
+            .map(|arr_data| (arr_data.instance_name(), arr_data.get_disk_size()));
+        let uhd = self
+            .arr_4k_data
+            .iter()
+            .map(|arr_data| (arr_data.instance_name(), arr_data.get_disk_size()));
+
+        standard.chain(uhd).collect()
+    }
+
+    /// The size of the 4K copy, when the item is also in a standard instance.
+    pub fn get_4k_duplicate_size(&self) -> Option<i64> {
+        match (self.arr_data.as_ref(), self.arr_4k_data.as_ref()) {
+            (Some(_), Some(arr_data_4k)) => Some(arr_data_4k.get_disk_size()),
+            _ => None,
         }
+    }
+
+    pub fn get_last_watched(&self) -> Option<DateTime<Utc>> {
+        self.history.last_watched()
+    }
+
+    pub fn get_request_age_days(&self) -> Option<i64> {
+        self.get_requested_date()
+            .map(|date| Utc::now().signed_duration_since(date).num_days())
+    }
 
-        if let Some(arr_data) = self.arr_data {
-            arr_data.remove_data().await?;
+    /// Finds the last episode on disk of a requested series, which is needed to tell whether the
+    /// requester has watched it. When Sonarr can't list the episodes, the series counts as not
+    /// watched by the requester, rather than being left out.
+    pub async fn find_last_available_episode(&mut self) {
+        if self.request.is_none() {
+            return;
         }
 
-        if let Some(arr_data) = self.arr_4k_data {
-            arr_data.remove_data().await?;
+        if let Some(arr_data) = self.arr_data.as_mut().or(self.arr_4k_data.as_mut()) {
+            let _ = arr_data.find_last_available_episode().await;
         }
+    }
 
-        Ok(())
+    /// Whether the requester has watched the item, according to the requester policy.
+    /// Always false when there is no policy configured or the item was never requested.
+    pub fn is_fulfilled(&self) -> bool {
+        Config::global().requester_policy.is_some() && self.is_watched_by_requester()
     }
 
-    pub fn get_requested_date(&self) -> Option<DateTime<Utc>> {
-        self.request.as_ref().map(|request| request.created_at)
+    /// Whether the requester has watched the item, using the progress from the requester policy
+    /// (or its default). Always false when the item was never requested.
+    pub fn is_watched_by_requester(&self) -> bool {
+        let request = match self.request {
+            Some(ref request) => request,
+            None => return false,
+        };
+
+        let min_progress = match Config::global().requester_policy {
+            Some(ref policy) => policy.min_progress,
+            None => default_min_progress(),
+        };
+        let last_episode = self
+            .get_arr_data()
+            .and_then(|arr_data| arr_data.last_available_episode());
+
+        self.history
+            .watched_by(&request.requester, min_progress, last_episode)
+    }
+
+    pub fn get_requester_usage(&self) -> i64 {
+        self.requester_usage
+    }
+
+    pub(crate) fn set_requester_usage(&mut self, usage: i64) {
+        self.requester_usage = usage;
+    }
+
+    /// Whether all gathered requests of the requester together take up more than their quota.
+    pub fn is_requester_over_quota(&self) -> bool {
+        let config = Config::global();
+        let (request, quota) = match (&self.request, &config.requester_quota) {
+            (Some(request), Some(quota)) => (request, quota),
+            _ => return false,
+        };
+
+        quota
+            .quota_for(&request.requester)
+            .is_some_and(|quota| self.requester_usage > quota)
     }
 
     pub fn get_disk_size(&self) -> i64 {
@@ -204,7 +294,7 @@ impl CompleteMediaItem {
         Ok(())
     }
 
-    fn status_4k(&self) -> &str {
+    pub fn status_4k(&self) -> &str {
         match (self.arr_data.as_ref(), self.arr_4k_data.as_ref()) {
             (Some(_), None) => "",
             (None, Some(_)) => "Only 4K ",
@@ -230,10 +320,14 @@ impl Display for CompleteMediaItem {
             write!(f, " {}", request)?;
         }
 
+        if self.is_fulfilled() {
+            write!(f, " {}", "Watched by requester.".green())?;
+        }
+
         self.print_arr_data(f)?;
 
         write!(f, "\n      {}", self.history)?;
 
-        write!(f, "\n")
+        writeln!(f)
     }
 }
@@ -0,0 +1,219 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::Utc;
+use color_eyre::Result;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+use std::{future::Future, net::SocketAddr, time::Instant};
+
+use crate::{media_item::CompleteMediaItem, notifications::RunSummary, requesters::RequesterKey};
+
+static CANDIDATE_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "media_cleaner_candidate_bytes",
+        "Size on disk of the items that can be cleaned up.",
+        &["media_type", "instance"]
+    )
+    .expect("Failed to register metric.")
+});
+
+static CANDIDATE_ITEMS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "media_cleaner_candidate_items",
+        "Number of items that can be cleaned up.",
+        &["media_type"]
+    )
+    .expect("Failed to register metric.")
+});
+
+static NEVER_WATCHED_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "media_cleaner_never_watched_bytes",
+        "Size on disk of the items that can be cleaned up, which nobody has ever watched.",
+        &["media_type"]
+    )
+    .expect("Failed to register metric.")
+});
+
+static REQUESTER_ITEMS: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!(
+        "media_cleaner_requester_items",
+        "Number of items that can be cleaned up per requester.",
+        &["requester_id", "requester"]
+    )
+    .expect("Failed to register metric.")
+});
+
+static LAST_GATHERED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "media_cleaner_last_gathered_timestamp_seconds",
+        "When the data was last gathered from the services."
+    )
+    .expect("Failed to register metric.")
+});
+
+static LAST_RUN_BYTES_FREED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "media_cleaner_last_run_bytes_freed",
+        "Bytes freed by the last cleanup."
+    )
+    .expect("Failed to register metric.")
+});
+
+static LAST_RUN_ITEMS_DELETED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "media_cleaner_last_run_items_deleted",
+        "Number of items removed by the last cleanup."
+    )
+    .expect("Failed to register metric.")
+});
+
+static LAST_RUN: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "media_cleaner_last_run_timestamp_seconds",
+        "When the last cleanup finished."
+    )
+    .expect("Failed to register metric.")
+});
+
+static DELETION_ERRORS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "media_cleaner_deletion_errors_total",
+        "Number of failed removals per service.",
+        &["service"]
+    )
+    .expect("Failed to register metric.")
+});
+
+static API_LATENCY: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "media_cleaner_api_request_duration_seconds",
+        "Time taken by the requests to each service.",
+        &["service"]
+    )
+    .expect("Failed to register metric.")
+});
+
+/// Registers every metric up front, so they are all exported before anything has been recorded.
+pub fn register() {
+    Lazy::force(&CANDIDATE_BYTES);
+    Lazy::force(&CANDIDATE_ITEMS);
+    Lazy::force(&NEVER_WATCHED_BYTES);
+    Lazy::force(&REQUESTER_ITEMS);
+    Lazy::force(&LAST_GATHERED);
+    Lazy::force(&LAST_RUN_BYTES_FREED);
+    Lazy::force(&LAST_RUN_ITEMS_DELETED);
+    Lazy::force(&LAST_RUN);
+    Lazy::force(&DELETION_ERRORS);
+    Lazy::force(&API_LATENCY);
+}
+
+/// Replaces the library metrics with the newly gathered items.
+pub fn record_candidates(items: &[CompleteMediaItem]) {
+    CANDIDATE_BYTES.reset();
+    CANDIDATE_ITEMS.reset();
+    NEVER_WATCHED_BYTES.reset();
+    REQUESTER_ITEMS.reset();
+
+    for item in items {
+        let media_type = item.media_type.to_string().to_lowercase();
+
+        for (instance, size) in item.get_instance_sizes() {
+            CANDIDATE_BYTES
+                .with_label_values(&[&media_type, instance])
+                .add(size);
+        }
+
+        CANDIDATE_ITEMS.with_label_values(&[&media_type]).inc();
+
+        if item.get_last_watched().is_none() {
+            NEVER_WATCHED_BYTES
+                .with_label_values(&[&media_type])
+                .add(item.get_disk_size());
+        }
+    }
+
+    // Requesters are told apart by their Overseerr id like everywhere else, so two users with the
+    // same name aren't merged and renaming someone doesn't start a new series.
+    items
+        .iter()
+        .filter_map(|item| item.get_request())
+        .into_group_map_by(|request| RequesterKey::of(request))
+        .into_iter()
+        .for_each(|(key, requests)| {
+            let id = key
+                .overseerr_id()
+                .map(|id| id.to_string())
+                .unwrap_or_default();
+            REQUESTER_ITEMS
+                .with_label_values(&[&id, &requests[0].requested_by])
+                .set(requests.len() as i64);
+        });
+
+    LAST_GATHERED.set(Utc::now().timestamp());
+}
+
+pub fn record_run(summary: &RunSummary) {
+    LAST_RUN_BYTES_FREED.set(summary.bytes_freed);
+    LAST_RUN_ITEMS_DELETED.set(summary.deleted.len() as i64);
+    LAST_RUN.set(Utc::now().timestamp());
+}
+
+pub fn record_deletion_error(service: &str) {
+    DELETION_ERRORS.with_label_values(&[service]).inc();
+}
+
+/// Awaits a request to one of the services, recording how long it took.
+pub async fn time_request<F: Future>(service: &str, request: F) -> F::Output {
+    let start = Instant::now();
+    let output = request.await;
+
+    API_LATENCY
+        .with_label_values(&[service])
+        .observe(start.elapsed().as_secs_f64());
+
+    output
+}
+
+/// All metrics in the Prometheus text format.
+fn render() -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&prometheus::gather(), &mut buffer)?;
+
+    Ok(buffer)
+}
+
+/// Handler for the `/metrics` route.
+pub async fn handler() -> Response {
+    match render() {
+        Ok(metrics) => (
+            [(
+                header::CONTENT_TYPE,
+                TextEncoder::new().format_type().to_string(),
+            )],
+            metrics,
+        )
+            .into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Serves only the metrics, for when the web UI is not running.
+pub async fn serve(address: SocketAddr) -> Result<()> {
+    register();
+    let app = Router::new().route("/metrics", get(handler));
+
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .await?;
+
+    Ok(())
+}
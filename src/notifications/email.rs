@@ -0,0 +1,33 @@
+use color_eyre::Result;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+
+use crate::config::{Smtp, SmtpSecurity};
+
+pub async fn send(smtp: &Smtp, to: &str, subject: &str, body: String) -> Result<()> {
+    let message = Message::builder()
+        .from(smtp.from.parse::<Mailbox>()?)
+        .to(to.parse::<Mailbox>()?)
+        .subject(subject)
+        .body(body)?;
+
+    let mut transport = match smtp.security {
+        SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)?,
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.host)?,
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host),
+    };
+
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport.build().send(message).await?;
+
+    Ok(())
+}
@@ -0,0 +1,189 @@
+mod email;
+mod summary;
+mod webhook;
+
+use chrono::{DateTime, Utc};
+use color_eyre::{Report, Result};
+use futures::future;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    config::{Config, RequesterNotifications},
+    media_item::CompleteMediaItem,
+    overseerr::UserContacts,
+    utils::human_file_size,
+};
+pub use summary::{ErrorSummary, ItemSummary, RunMode, RunSummary, WebhookFormat};
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RemovalEvent {
+    Upcoming { removal_date: DateTime<Utc> },
+    Removed,
+}
+
+/// A single requested item, as it is told to the user who requested it.
+#[derive(Debug, Clone, Serialize)]
+pub struct RequesterNotice {
+    #[serde(skip)]
+    user_id: u32,
+    #[serde(skip)]
+    requested_by: String,
+    #[serde(flatten)]
+    pub item: ItemSummary,
+}
+
+impl RequesterNotice {
+    pub fn new(user_id: u32, requested_by: String, item: ItemSummary) -> Self {
+        Self {
+            user_id,
+            requested_by,
+            item,
+        }
+    }
+
+    pub fn from_item(item: &CompleteMediaItem) -> Option<Self> {
+        let request = item.get_request()?;
+
+        Some(Self::new(
+            request.requester.overseerr_id?,
+            request.requested_by.clone(),
+            ItemSummary::from_item(item),
+        ))
+    }
+
+    pub fn user_id(&self) -> u32 {
+        self.user_id
+    }
+
+    pub fn requested_by(&self) -> &str {
+        &self.requested_by
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    #[serde(flatten)]
+    event: RemovalEvent,
+    requested_by: &'a str,
+    contacts: &'a UserContacts,
+    items: &'a [RequesterNotice],
+}
+
+/// Tells every requester which of their items are about to be, or have been, removed.
+/// Returns the errors per requester, as a failed notification should not stop the deletion.
+pub async fn notify_requesters(
+    notices: Vec<RequesterNotice>,
+    event: RemovalEvent,
+) -> Vec<(String, Report)> {
+    let global_config = Config::global();
+    let config = match global_config.requester_notifications {
+        Some(ref config) => config,
+        None => return Vec::new(),
+    };
+
+    let futures = notices
+        .into_iter()
+        .into_group_map_by(|notice| notice.user_id)
+        .into_values()
+        .map(|notices| async move {
+            notify_requester(config, &notices, event)
+                .await
+                .map_err(|err| (notices[0].requested_by.clone(), err))
+        });
+
+    future::join_all(futures)
+        .await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect()
+}
+
+async fn notify_requester(
+    config: &RequesterNotifications,
+    notices: &[RequesterNotice],
+    event: RemovalEvent,
+) -> Result<()> {
+    let requested_by = &notices[0].requested_by;
+    let contacts = UserContacts::get(notices[0].user_id).await?;
+
+    if let Some(ref smtp) = config.smtp {
+        let (subject, body) = create_email_text(requested_by, notices, event);
+        email::send(smtp, &contacts.email, &subject, body).await?;
+    }
+
+    if let Some(ref url) = config.webhook_url {
+        let payload = WebhookPayload {
+            event,
+            requested_by,
+            contacts: &contacts,
+            items: notices,
+        };
+        webhook::post(url, &payload, config.webhook_timeout_seconds).await?;
+    }
+
+    Ok(())
+}
+
+fn create_email_text(
+    requested_by: &str,
+    notices: &[RequesterNotice],
+    event: RemovalEvent,
+) -> (String, String) {
+    let items = notices
+        .iter()
+        .map(|notice| {
+            format!(
+                "- {} ({}, {})",
+                notice.item.title,
+                notice.item.media_type,
+                human_file_size(notice.item.size)
+            )
+        })
+        .join("\n");
+
+    match event {
+        RemovalEvent::Upcoming { removal_date } => (
+            "Some of your requests are about to be removed".to_string(),
+            format!(
+                "Hi {},\n\nThe following media you requested will be removed on {}:\n\n{}\n\nIf you still want to watch any of it, now is the time.\n",
+                requested_by,
+                removal_date.format("%d-%m-%Y"),
+                items
+            ),
+        ),
+        RemovalEvent::Removed => (
+            "Some of your requests have been removed".to_string(),
+            format!(
+                "Hi {},\n\nThe following media you requested has now been removed:\n\n{}\n",
+                requested_by, items
+            ),
+        ),
+    }
+}
+
+/// Posts the summary of a run to every configured webhook, returning the errors per webhook.
+pub async fn send_run_summary(summary: &RunSummary) -> Vec<(String, Report)> {
+    let global_config = Config::global();
+    let webhooks = match global_config.webhooks {
+        Some(ref webhooks) => webhooks,
+        None => return Vec::new(),
+    };
+
+    let futures = webhooks.iter().map(|hook| async move {
+        webhook::post(
+            &hook.url,
+            &summary.to_payload(hook.format),
+            hook.timeout_seconds,
+        )
+        .await
+        .map_err(|err| (webhook::host(&hook.url), err))
+    });
+
+    future::join_all(futures)
+        .await
+        .into_iter()
+        .filter_map(Result::err)
+        .collect()
+}
@@ -0,0 +1,205 @@
+use color_eyre::Report;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    deletion::ItemOutcome, media_item::CompleteMediaItem, shared::MediaType, utils::human_file_size,
+};
+
+/// The most items listed in a chat message, to stay within Discord's and Slack's message limits.
+const MAX_LISTED_ITEMS: usize = 20;
+/// The longest description Discord accepts in an embed.
+const DISCORD_MAX_LENGTH: usize = 4096;
+/// The longest text Slack accepts in a section block.
+const SLACK_MAX_LENGTH: usize = 3000;
+/// The room kept for the line saying how many more items there are.
+const MORE_LINE_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunMode {
+    Interactive,
+    Scheduled,
+    Web,
+    /// Started by another program through the library.
+    Library,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    #[default]
+    Json,
+    Discord,
+    Slack,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSummary {
+    pub rating_key: String,
+    pub title: String,
+    pub media_type: MediaType,
+    pub size: i64,
+}
+
+impl ItemSummary {
+    pub fn from_item(item: &CompleteMediaItem) -> Self {
+        Self {
+            rating_key: item.rating_key.clone(),
+            title: item.title.clone(),
+            media_type: item.media_type,
+            size: item.get_disk_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorSummary {
+    pub title: String,
+    pub error: String,
+}
+
+impl ErrorSummary {
+    pub fn from_errors(errors: &[(String, Report)]) -> Vec<Self> {
+        errors
+            .iter()
+            .map(|(title, err)| Self {
+                title: title.clone(),
+                error: err.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub mode: RunMode,
+    pub deleted: Vec<ItemSummary>,
+    pub bytes_freed: i64,
+    pub errors: Vec<ErrorSummary>,
+    pub outcomes: Vec<ItemOutcome>,
+}
+
+impl RunSummary {
+    pub fn new(
+        mode: RunMode,
+        deleted: Vec<ItemSummary>,
+        errors: &[(String, Report)],
+        outcomes: Vec<ItemOutcome>,
+    ) -> Self {
+        Self {
+            mode,
+            bytes_freed: deleted.iter().map(|item| item.size).sum(),
+            deleted,
+            errors: ErrorSummary::from_errors(errors),
+            outcomes,
+        }
+    }
+
+    pub fn to_payload(&self, format: WebhookFormat) -> Value {
+        match format {
+            WebhookFormat::Json => json!(self),
+            WebhookFormat::Discord => json!({
+                "username": "Media Cleaner",
+                "embeds": [{
+                    "title": self.headline(),
+                    "description": self.details(DISCORD_MAX_LENGTH),
+                    "color": if self.errors.is_empty() { 0x2ecc71 } else { 0xe74c3c },
+                }],
+            }),
+            WebhookFormat::Slack => json!({
+                "text": self.headline(),
+                "blocks": [
+                    {
+                        "type": "header",
+                        "text": { "type": "plain_text", "text": self.headline() },
+                    },
+                    {
+                        "type": "section",
+                        "text": { "type": "mrkdwn", "text": self.details(SLACK_MAX_LENGTH) },
+                    },
+                ],
+            }),
+        }
+    }
+
+    fn headline(&self) -> String {
+        let mode = match self.mode {
+            RunMode::Interactive => "an interactive",
+            RunMode::Scheduled => "a scheduled",
+            RunMode::Web => "a web UI",
+            RunMode::Library => "an embedded",
+        };
+
+        format!(
+            "Media Cleaner finished {} run, freeing {}",
+            mode,
+            human_file_size(self.bytes_freed)
+        )
+    }
+
+    /// What was deleted and what went wrong, in at most `max_length` characters. Items that don't
+    /// fit are counted on an "and N more" line instead.
+    fn details(&self, max_length: usize) -> String {
+        let mut details = format!("Deleted {} items.", self.deleted.len());
+        let errors_heading = format!("\n\nGot {} errors:\n", self.errors.len());
+
+        if !self.deleted.is_empty() {
+            let mut room = max_length.saturating_sub(details.chars().count() + 1);
+            // Leave the errors at least half of the room, they are what needs looking into.
+            if !self.errors.is_empty() {
+                room = room.saturating_sub(errors_heading.chars().count()) / 2;
+            }
+
+            details.push('\n');
+            details.push_str(&list_lines(
+                self.deleted.iter().map(|item| {
+                    format!(
+                        "- {} ({}, {})",
+                        item.title,
+                        item.media_type,
+                        human_file_size(item.size)
+                    )
+                }),
+                room,
+            ));
+        }
+
+        if !self.errors.is_empty() {
+            details.push_str(&errors_heading);
+            let room = max_length.saturating_sub(details.chars().count());
+            details.push_str(&list_lines(
+                self.errors
+                    .iter()
+                    .map(|err| format!("- {}: {}", err.title, err.error)),
+                room,
+            ));
+        }
+
+        details
+    }
+}
+
+/// Lists the lines for as long as they fit in `max_length` characters, up to `MAX_LISTED_ITEMS`.
+fn list_lines(lines: impl ExactSizeIterator<Item = String>, max_length: usize) -> String {
+    let total = lines.len();
+    let mut listed = Vec::new();
+    let mut length = 0;
+
+    for line in lines.take(MAX_LISTED_ITEMS) {
+        let line_length = line.chars().count() + 1;
+        if length + line_length + MORE_LINE_LENGTH > max_length {
+            break;
+        }
+
+        length += line_length;
+        listed.push(line);
+    }
+
+    let hidden = total - listed.len();
+    if hidden > 0 {
+        listed.push(format!("...and {} more.", hidden));
+    }
+
+    listed.join("\n")
+}
@@ -0,0 +1,51 @@
+use color_eyre::Result;
+use reqwest::Url;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::{
+    error::{ErrorKind, ServiceError},
+    http,
+};
+
+/// Posts the payload to the webhook, giving up after the timeout so a slow webhook can't hold up
+/// the end of a run.
+pub async fn post<T>(url: &str, payload: &T, timeout_seconds: u64) -> Result<()>
+where
+    T: Serialize,
+{
+    let endpoint = host(url);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(url)
+        .json(payload)
+        .timeout(Duration::from_secs(timeout_seconds))
+        .send()
+        .await
+        .map_err(|err| {
+            if err.is_timeout() {
+                let cause = format!("within {} seconds", timeout_seconds);
+                ServiceError::request("Webhook", &endpoint, ErrorKind::Timeout, cause, 1)
+            } else {
+                let cause = http::root_cause(err);
+                ServiceError::request("Webhook", &endpoint, ErrorKind::Network, cause, 1)
+            }
+        })?;
+
+    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+        return Err(ServiceError::from_response("Webhook", &endpoint, response)
+            .await
+            .into());
+    }
+
+    Ok(())
+}
+
+/// The host of the webhook, to name it by in errors. Discord and Slack webhook URLs hold their
+/// token, so the full URL is never shown.
+pub fn host(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_string()))
+        .unwrap_or_else(|| "the webhook".to_string())
+}
@@ -1,67 +1,145 @@
-use color_eyre::{eyre::eyre, Result};
-use serde::de::DeserializeOwned;
+use color_eyre::Result;
+use serde::{de::DeserializeOwned, Serialize};
 
 use super::responses::RequestResponse;
 use crate::{
-    config::Config,
-    utils::{create_api_error_message, create_param_string},
+    cache,
+    config::{Config, Seerr},
+    error::ServiceError,
+    http::{self, ServiceClient},
+    utils::create_param_string,
 };
 
 pub async fn get<T>(path: &str, params: Option<Vec<(&str, &str)>>) -> Result<RequestResponse<T>>
 where
     T: DeserializeOwned,
 {
-    let client = reqwest::Client::new();
     let config = &Config::global().seerr;
-    let response = client
-        .get(format!(
-            "{}/api/v1{}?take=100&{}",
+    let client = http::client("overseerr", "overseerr", &config.requests);
+    let url = format!(
+        "{}/api/v1{}?take=100&{}",
+        &config.url,
+        path,
+        &create_param_string(params)
+    );
+    let mut response_data: RequestResponse<T> = get_json(&client, &url, path).await?;
+
+    let page_size = response_data.page_info.page_size;
+    for page in 1..response_data.page_info.pages {
+        let url = format!(
+            "{}/api/v1{}?take={}&skip={}",
             &config.url,
             path,
-            &create_param_string(params)
-        ))
-        .header("X-API-Key", &config.api_key)
-        .send()
-        .await?;
+            page_size,
+            page_size * page
+        );
+        let mut page_data: RequestResponse<T> = get_json(&client, &url, path).await?;
+
+        response_data.results.append(&mut page_data.results);
+    }
+
+    Ok(response_data)
+}
+
+pub async fn get_obj<T>(path: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let config = &Config::global().seerr;
+    let client = http::client("overseerr", "overseerr", &config.requests);
+    let url = format!("{}/api/v1{}", &config.url, path);
+
+    let response = get_json(&client, &url, path).await?;
+
+    Ok(response)
+}
+
+/// Gets settings with the given Overseerr config, which does not have to be the current one.
+/// These are never cached, as they hold the API keys of the other services.
+pub async fn get_settings<T>(config: &Seerr, path: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let client = http::client("overseerr", "overseerr", &config.requests);
+
+    let request = client
+        .get(&format!("{}/api/v1{}", &config.url, path))
+        .header("X-API-Key", &config.api_key);
+    let response = client.send(request).await?;
 
     if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
-        let code = response.status().as_u16();
-        return Err(eyre!(create_api_error_message(code, path, "Overseerr")));
+        return Err(ServiceError::from_response("Overseerr", path, response)
+            .await
+            .into());
     }
 
-    let mut response_data: RequestResponse<T> = response.json().await?;
+    let body = client.text(path, response).await?;
 
-    let page_size = response_data.page_info.page_size;
-    for page in 1..response_data.page_info.pages {
-        let mut page_data: RequestResponse<T> = client
-            .get(format!(
-                "{}/api/v1{}?take={}&skip={}",
-                &config.url,
-                path,
-                page_size,
-                page_size * page
-            ))
-            .header("X-API-Key", &config.api_key)
-            .send()
-            .await?
-            .json()
-            .await?;
+    Ok(serde_json::from_str(&body)
+        .map_err(|err| ServiceError::parse("Overseerr", path, err, &body))?)
+}
 
-        response_data.results.append(&mut page_data.results);
+/// Gets the response, from the cache if it was fetched recently.
+async fn get_json<T>(client: &ServiceClient, url: &str, path: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let body = cache::get_or_fetch("overseerr", url, || async {
+        let request = client
+            .get(url)
+            .header("X-API-Key", &Config::global().seerr.api_key);
+        let response = client.send(request).await?;
+
+        if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+            return Err(ServiceError::from_response("Overseerr", path, response)
+                .await
+                .into());
+        }
+
+        client.text(path, response).await
+    })
+    .await?;
+
+    Ok(serde_json::from_str(&body)
+        .map_err(|err| ServiceError::parse("Overseerr", path, err, &body))?)
+}
+
+pub async fn post<T>(path: &str, body: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    let config = &Config::global().seerr;
+    let client = http::client("overseerr", "overseerr", &config.requests);
+
+    let request = client
+        .post(&format!("{}/api/v1{}", &config.url, path))
+        .header("X-API-Key", &config.api_key)
+        .json(body);
+    let response = client.send(request).await?;
+
+    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+        return Err(ServiceError::from_response("Overseerr", path, response)
+            .await
+            .into());
     }
 
-    Ok(response_data)
+    Ok(())
 }
 
 pub async fn delete(path: &str) -> Result<()> {
     let config = &Config::global().seerr;
-    let client = reqwest::Client::new();
+    let client = http::client("overseerr", "overseerr", &config.requests);
 
-    client
-        .delete(format!("{}/api/v1{}", &config.url, path))
-        .header("X-API-Key", &config.api_key)
-        .send()
-        .await?;
+    let request = client
+        .delete(&format!("{}/api/v1{}", &config.url, path))
+        .header("X-API-Key", &config.api_key);
+    let response = client.send(request).await?;
+
+    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+        return Err(ServiceError::from_response("Overseerr", path, response)
+            .await
+            .into());
+    }
 
     Ok(())
 }
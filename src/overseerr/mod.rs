@@ -3,12 +3,18 @@ mod responses;
 
 use chrono::prelude::*;
 use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Result};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-use self::responses::MediaResponse;
+use self::responses::{
+    ArrServerResponse, MainSettingsResponse, MediaResponse, NotificationSettingsResponse,
+    StatusResponse, UserResponse,
+};
 use crate::{
+    config::Seerr,
     overseerr::responses::{MediaRequestResponse, RequestResponse},
     shared::MediaType,
+    users::{UserIdentity, UserRegistry},
 };
 pub use responses::MediaStatus;
 
@@ -22,18 +28,115 @@ pub struct MediaRequest {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub requested_by: String,
+    pub requester: UserIdentity,
     pub media_status: responses::MediaStatus,
     pub media_type: MediaType,
+    pub tmdb_id: Option<u32>,
+    pub is_4k: bool,
 }
 
-impl MediaRequest {
-    pub async fn remove_request(self) -> Result<()> {
-        let path = format!("/media/{}", self.media_id);
-        api::delete(&path).await?;
+/// What is needed to request an item again, on behalf of the user who first requested it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestRecord {
+    pub media_type: MediaType,
+    pub tmdb_id: Option<u32>,
+    pub is_4k: bool,
+    pub user_id: Option<u32>,
+    pub requested_by: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NewRequest {
+    media_type: MediaType,
+    media_id: u32,
+    is_4k: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seasons: Option<&'static str>,
+}
 
-        Ok(())
+/// A Sonarr or Radarr server as it is set up in Overseerr.
+#[derive(Debug, Clone)]
+pub struct ArrServer {
+    pub name: String,
+    pub url: String,
+    pub api_key: String,
+    pub is_4k: bool,
+}
+
+impl ArrServer {
+    fn from_response(server: ArrServerResponse) -> Self {
+        let scheme = if server.use_ssl { "https" } else { "http" };
+
+        Self {
+            name: server.name,
+            url: format!(
+                "{}://{}:{}{}",
+                scheme,
+                server.hostname,
+                server.port,
+                server.base_url.unwrap_or_default()
+            ),
+            api_key: server.api_key,
+            is_4k: server.is4k,
+        }
     }
+}
+
+pub async fn get_version() -> Result<String> {
+    let status: StatusResponse = api::get_obj("/status").await?;
+    Ok(status.version)
+}
+
+/// Whether CSRF protection is turned on, which makes Overseerr refuse removals through the API.
+pub async fn csrf_protection() -> Result<bool> {
+    let settings: MainSettingsResponse = api::get_obj("/settings/main").await?;
+    Ok(settings.csrf_protection)
+}
 
+/// The Radarr servers for movies, or the Sonarr servers for shows. This takes the Overseerr
+/// section of the config, as the servers are looked up before the config is used.
+pub async fn get_arr_servers(config: &Seerr, media_type: MediaType) -> Result<Vec<ArrServer>> {
+    let path = match media_type {
+        MediaType::Movie => "/settings/radarr",
+        MediaType::Tv => "/settings/sonarr",
+    };
+    let servers: Vec<ArrServerResponse> = api::get_settings(config, path).await?;
+
+    Ok(servers.into_iter().map(ArrServer::from_response).collect())
+}
+
+/// Removes the media from Overseerr, along with all of its requests.
+pub async fn remove_media(media_id: u32) -> Result<()> {
+    let path = format!("/media/{}", media_id);
+    api::delete(&path).await?;
+
+    Ok(())
+}
+
+/// Makes the request again, for the user who made the original one if they are known.
+/// Series are requested with all of their seasons.
+pub async fn restore_request(record: &RequestRecord) -> Result<()> {
+    let media_id = record.tmdb_id.ok_or_else(|| {
+        eyre!("The request can not be made again, as the TMDB ID of the item is unknown.")
+    })?;
+    let request = NewRequest {
+        media_type: record.media_type,
+        media_id,
+        is_4k: record.is_4k,
+        user_id: record.user_id,
+        seasons: match record.media_type {
+            MediaType::Tv => Some("all"),
+            MediaType::Movie => None,
+        },
+    };
+
+    api::post("/request", &request).await
+}
+
+impl MediaRequest {
     pub async fn get_all() -> Result<Vec<Self>> {
         let response_data: RequestResponse<MediaRequestResponse> =
             api::get("/request", None).await?;
@@ -64,6 +167,8 @@ impl MediaRequest {
             None => response.requested_by.email.clone(),
         };
 
+        let requester = UserRegistry::global().resolve(response.requested_by.into());
+
         Ok(MediaRequest {
             id: response.id,
             media_id: response.media.id,
@@ -74,11 +179,26 @@ impl MediaRequest {
             updated_at: updated_at.with_timezone(&Utc),
             media_status: response.media.status,
             media_type: response.media.media_type,
+            tmdb_id: response.media.tmdb_id,
+            is_4k: response.is_4k,
             requested_by,
+            requester,
         })
     }
 }
 
+impl MediaRequest {
+    pub fn record(&self) -> RequestRecord {
+        RequestRecord {
+            media_type: self.media_type,
+            tmdb_id: self.tmdb_id,
+            is_4k: self.is_4k,
+            user_id: self.requester.overseerr_id,
+            requested_by: self.requested_by.clone(),
+        }
+    }
+}
+
 impl Display for MediaRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -94,14 +214,56 @@ impl Display for MediaRequest {
     }
 }
 
+pub async fn get_users() -> Result<Vec<UserIdentity>> {
+    let response_data: RequestResponse<UserResponse> = api::get("/user", None).await?;
+
+    Ok(response_data.results.into_iter().map(Into::into).collect())
+}
+
+/// Where a user can be reached, according to their Overseerr profile and notification settings.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserContacts {
+    pub email: String,
+    pub discord_id: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub pushover_user_key: Option<String>,
+}
+
+impl UserContacts {
+    pub async fn get(user_id: u32) -> Result<Self> {
+        let user: UserResponse = api::get_obj(&format!("/user/{}", user_id)).await?;
+        let settings: NotificationSettingsResponse =
+            api::get_obj(&format!("/user/{}/settings/notifications", user_id)).await?;
+
+        Ok(Self {
+            email: user.email,
+            discord_id: settings.discord_id.filter(|id| !id.is_empty()),
+            telegram_chat_id: settings.telegram_chat_id.filter(|id| !id.is_empty()),
+            pushover_user_key: settings.pushover_user_key.filter(|key| !key.is_empty()),
+        })
+    }
+}
+
+impl From<UserResponse> for UserIdentity {
+    fn from(user: UserResponse) -> Self {
+        UserIdentity::new(
+            Some(user.id),
+            user.plex_id,
+            vec![
+                user.display_name,
+                user.username,
+                user.plex_username,
+                Some(user.email),
+            ],
+        )
+    }
+}
+
 #[derive(Debug)]
 pub struct ServerItem {
-    pub id: u32,
     pub rating_key: String,
     pub manager_id: Option<i32>,
     pub manager_id_4k: Option<i32>,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
     pub media_status: responses::MediaStatus,
     pub media_type: MediaType,
 }
@@ -126,14 +288,7 @@ impl ServerItem {
     }
 
     fn from_response(response: MediaResponse) -> Result<Self> {
-        let created_at = DateTime::parse_from_rfc3339(&response.created_at)?;
-        let updated_at = match &response.updated_at {
-            Some(updated_at) => DateTime::parse_from_rfc3339(updated_at)?,
-            None => created_at,
-        };
-
         Ok(Self {
-            id: response.id,
             rating_key: match response.rating_key {
                 Some(rating_key) => rating_key,
                 None => {
@@ -146,8 +301,6 @@ impl ServerItem {
             },
             manager_id: response.external_service_id,
             manager_id_4k: response.external_service_id_4k,
-            created_at: created_at.with_timezone(&Utc),
-            updated_at: updated_at.with_timezone(&Utc),
             media_status: response.status,
             media_type: response.media_type,
         })
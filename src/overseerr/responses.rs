@@ -2,7 +2,6 @@ use color_eyre::owo_colors::OwoColorize;
 use serde::Deserialize;
 use serde_repr::Deserialize_repr;
 use std::fmt::Display;
-use openssl::pkey::Public;
 use crate::shared::MediaType;
 
 #[derive(Debug, Deserialize)]
@@ -15,9 +14,7 @@ pub struct RequestResponse<T> {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PageInfo {
-    pub page: u32,
     pub pages: u32,
-    pub results: u32,
     pub page_size: u32,
 }
 
@@ -27,19 +24,29 @@ pub struct UserResponse {
     pub id: u32,
     pub email: String,
     pub display_name: Option<String>,
+    pub username: Option<String>,
+    pub plex_id: Option<u32>,
+    pub plex_username: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettingsResponse {
+    pub discord_id: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub pushover_user_key: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MediaResponse {
     pub id: u32,
+    pub tmdb_id: Option<u32>,
     pub external_service_id: Option<i32>,
     pub external_service_id_4k: Option<i32>,
     pub rating_key: Option<String>,
     pub status: MediaStatus,
     pub media_type: MediaType,
-    pub created_at: String,
-    pub updated_at: Option<String>,
 }
 
 #[derive(Debug, Deserialize_repr, Clone, Copy)]
@@ -57,13 +64,13 @@ pub enum MediaStatus {
 impl Display for MediaStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Unknown => write!(f, "{}", "Unknown".red().to_string()),
-            Self::Pending => write!(f, "{}", "Pending".yellow().to_string()),
-            Self::Processing => write!(f, "{}", "Processing".yellow().to_string()),
-            Self::PartiallyAvailable => write!(f, "{}", "Partially Available".blue().to_string()),
-            Self::Available => write!(f, "{}", "Available".green().to_string()),
-            Self::Blocklisted => write!(f, "{}", "Blocklisted".red().to_string()),
-            Self::Deleted => write!(f, "{}", "Deleted".red().to_string()),
+            Self::Unknown => write!(f, "{}", "Unknown".red()),
+            Self::Pending => write!(f, "{}", "Pending".yellow()),
+            Self::Processing => write!(f, "{}", "Processing".yellow()),
+            Self::PartiallyAvailable => write!(f, "{}", "Partially Available".blue()),
+            Self::Available => write!(f, "{}", "Available".green()),
+            Self::Blocklisted => write!(f, "{}", "Blocklisted".red()),
+            Self::Deleted => write!(f, "{}", "Deleted".red()),
         }
     }
 }
@@ -73,7 +80,34 @@ impl Display for MediaStatus {
 pub struct MediaRequestResponse {
     pub id: u32,
     pub media: MediaResponse,
+    #[serde(default)]
+    pub is_4k: bool,
     pub created_at: String,
     pub updated_at: Option<String>,
     pub requested_by: UserResponse,
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MainSettingsResponse {
+    #[serde(default)]
+    pub csrf_protection: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArrServerResponse {
+    pub name: String,
+    pub hostname: String,
+    pub port: u16,
+    pub api_key: String,
+    pub use_ssl: bool,
+    pub base_url: Option<String>,
+    pub is4k: bool,
+}
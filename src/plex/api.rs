@@ -1,35 +1,34 @@
 use color_eyre::Result;
 use serde::de::DeserializeOwned;
 
-use crate::{
-    config::Config,
-    utils::{create_api_error_message, create_param_string},
-};
-use color_eyre::eyre::eyre;
+use crate::{cache, config::Config, error::ServiceError, http, utils::create_param_string};
 
 pub async fn get<T>(path: &str, params: Option<Vec<(&str, &str)>>) -> Result<T>
 where
     T: DeserializeOwned,
 {
     let config = &Config::global().plex;
-    let client = reqwest::Client::new();
+    let client = http::client("plex", "plex", &config.requests);
     let params = create_param_string(params);
 
-    let response = client
-        .get(format!(
-            "{}{}?X-Plex-Token={}&{}",
-            config.url, path, config.token, params
-        ))
-        .send()
-        .await?;
+    let key = format!("{}{}?{}", config.url, path, params);
+    let response_text = cache::get_or_fetch("plex", &key, || async {
+        let request = client
+            .get(&format!("{}{}?{}", config.url, path, params))
+            .header("X-Plex-Token", &config.token);
+        let response = client.send(request).await?;
 
-    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
-        let code = response.status().as_u16();
-        return Err(eyre!(create_api_error_message(code, path, "Plex")));
-    }
+        if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+            return Err(ServiceError::from_response("Plex", path, response)
+                .await
+                .into());
+        }
 
-    let response_text = response.text().await?;
-    let parsed_response: T = serde_xml_rs::from_str(&response_text)?;
+        client.text(path, response).await
+    })
+    .await?;
+    let parsed_response: T = serde_xml_rs::from_str(&response_text)
+        .map_err(|err| ServiceError::parse("Plex", path, err, &response_text))?;
 
     Ok(parsed_response)
 }
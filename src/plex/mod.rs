@@ -1,34 +1,66 @@
 mod api;
 mod responses;
 
-use crate::{plex::responses::MovieData, shared::MediaType};
+use crate::users::UserIdentity;
 
-use self::responses::TvData;
+use self::responses::{AccountsData, IdentityData, SectionItemsData, SectionsData};
 
 use color_eyre::Result;
+use futures::future;
+use std::collections::HashMap;
 
-pub struct PlexData {
-    pub title: String,
+pub async fn get_version() -> Result<String> {
+    let identity: IdentityData = api::get("/identity", None).await?;
+    Ok(identity.version)
 }
 
-impl PlexData {
-    pub async fn get_data(rating_key: &str, media_type: MediaType) -> Result<Self> {
-        let path = format!("/library/metadata/{}", rating_key);
-        match media_type {
-            MediaType::Movie => {
-                let raw_plex_data: MovieData = api::get(&path, None).await?;
-
-                Ok(Self {
-                    title: raw_plex_data.video.title,
-                })
-            }
-            MediaType::Tv => {
-                let raw_plex_data: TvData = api::get(&path, None).await?;
-
-                Ok(Self {
-                    title: raw_plex_data.directory.title,
-                })
-            }
-        }
-    }
+/// The number of movie and show libraries. Unlike the version, this needs a valid token.
+pub async fn count_libraries() -> Result<usize> {
+    let sections: SectionsData = api::get("/library/sections", None).await?;
+
+    Ok(sections
+        .sections
+        .iter()
+        .filter(|section| section.kind == "movie" || section.kind == "show")
+        .count())
+}
+
+/// Gets the titles of everything in the movie and show libraries, by rating key.
+pub async fn get_titles() -> Result<HashMap<String, String>> {
+    let sections: SectionsData = api::get("/library/sections", None).await?;
+
+    let section_items = sections
+        .sections
+        .into_iter()
+        .filter(|section| section.kind == "movie" || section.kind == "show")
+        .map(|section| async move {
+            let path = format!("/library/sections/{}/all", section.key);
+            api::get::<SectionItemsData>(&path, None).await
+        });
+
+    Ok(future::try_join_all(section_items)
+        .await?
+        .into_iter()
+        .flat_map(|items| items.videos.into_iter().chain(items.directories))
+        .map(|item| (item.rating_key, item.title))
+        .collect())
+}
+
+pub async fn get_accounts() -> Result<Vec<UserIdentity>> {
+    let raw_accounts: AccountsData = api::get("/accounts", None).await?;
+
+    Ok(raw_accounts
+        .accounts
+        .into_iter()
+        .filter(|account| !account.name.is_empty())
+        .map(|account| {
+            // The server owner always has the local id 1, which is not their Plex account id.
+            let plex_id = match account.id {
+                0 | 1 => None,
+                id => Some(id),
+            };
+
+            UserIdentity::new(None, plex_id, vec![Some(account.name)])
+        })
+        .collect())
 }
@@ -1,23 +1,46 @@
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
-pub struct TvData {
-    #[serde(rename = "Directory")]
-    pub directory: Directory,
+pub struct IdentityData {
+    pub version: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct Directory {
-    pub title: String,
+pub struct SectionsData {
+    #[serde(rename = "Directory", default)]
+    pub sections: Vec<Section>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct MovieData {
-    #[serde(rename = "Video")]
-    pub video: Video,
+pub struct Section {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub kind: String,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct Video {
+pub struct SectionItemsData {
+    #[serde(rename = "Video", default)]
+    pub videos: Vec<SectionItem>,
+    #[serde(rename = "Directory", default)]
+    pub directories: Vec<SectionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SectionItem {
+    #[serde(rename = "ratingKey")]
+    pub rating_key: String,
     pub title: String,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct AccountsData {
+    #[serde(rename = "Account", default)]
+    pub accounts: Vec<Account>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Account {
+    pub id: u32,
+    pub name: String,
+}
@@ -0,0 +1,45 @@
+use std::borrow::Borrow;
+
+use crate::{config::Config, media_item::CompleteMediaItem};
+
+/// Drops requests the requester has not watched yet, unless the request is older than the
+/// configured hold time. Items without a request are left alone.
+pub fn hold_back_unfulfilled(items: Vec<CompleteMediaItem>) -> Vec<CompleteMediaItem> {
+    let config = Config::global();
+    let policy = match config.requester_policy {
+        Some(ref policy) => policy,
+        None => return items,
+    };
+
+    items
+        .into_iter()
+        .filter(|item| match item.get_request_age_days() {
+            Some(age) => age >= policy.hold_days || item.is_fulfilled(),
+            None => true,
+        })
+        .collect()
+}
+
+/// Moves fulfilled requests to the front, keeping the chosen sorting within both groups.
+pub fn prioritise_fulfilled<T: Borrow<CompleteMediaItem>>(items: &mut [T]) {
+    if Config::global().requester_policy.is_none() {
+        return;
+    }
+
+    items.sort_by_key(|item| !item.borrow().is_fulfilled());
+}
+
+/// Moves the requests of requesters over their quota, that they have not watched themselves, to
+/// the front. The quotas are measured against all gathered items, not just the given ones.
+pub fn prioritise_over_quota<T: Borrow<CompleteMediaItem>>(items: &mut [T]) {
+    if Config::global().requester_quota.is_none() {
+        return;
+    }
+
+    items.sort_by_key(|item| {
+        let item = item.borrow();
+        let prioritised = item.is_requester_over_quota() && !item.is_watched_by_requester();
+
+        !prioritised
+    });
+}
@@ -0,0 +1,178 @@
+use std::fmt::{Result, Write};
+
+use super::{Group, ReportItem, TopList, WasteReport};
+use crate::requesters::RequesterStats;
+use crate::utils::human_file_size;
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; color: #222; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: 0.4rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; }
+td.number, th.number { text-align: right; }";
+
+pub fn render(report: &WasteReport) -> std::result::Result<String, std::fmt::Error> {
+    let mut out = String::new();
+
+    writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Library waste report</title>")?;
+    writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+
+    writeln!(out, "<h1>Library waste report</h1>")?;
+    writeln!(
+        out,
+        "<p>Generated on {}, covering {} items taking up {}.</p>",
+        report.generated_at.format("%d/%m/%Y"),
+        report.total_items,
+        human_file_size(report.total_size)
+    )?;
+    if report.gather_errors > 0 {
+        writeln!(
+            out,
+            "<p>{} items could not be gathered and are left out.</p>",
+            report.gather_errors
+        )?;
+    }
+
+    writeln!(out, "<h2>Largest never watched items</h2>")?;
+    write_summary(&mut out, &report.never_watched, "have never been watched")?;
+    write_items(&mut out, &report.never_watched, "Size")?;
+
+    writeln!(out, "<h2>Space by requester</h2>")?;
+    write_requesters(&mut out, &report.by_requester)?;
+    write_not_requested(&mut out, &report.not_requested)?;
+
+    writeln!(out, "<h2>Space by last watched</h2>")?;
+    write_groups(&mut out, &report.by_last_watched, "Last watched")?;
+
+    writeln!(out, "<h2>4K duplicates</h2>")?;
+    write_summary(
+        &mut out,
+        &report.duplicates_4k,
+        "are in both a standard and a 4K instance, with the 4K copies",
+    )?;
+    write_items(&mut out, &report.duplicates_4k, "4K size")?;
+
+    writeln!(out, "<h2>Ended series without viewers in a year</h2>")?;
+    write_summary(
+        &mut out,
+        &report.stale_series,
+        "have ended and not been watched in a year",
+    )?;
+    write_items(&mut out, &report.stale_series, "Size")?;
+
+    writeln!(out, "</body>\n</html>")?;
+
+    Ok(out)
+}
+
+fn write_summary(out: &mut String, list: &TopList, description: &str) -> Result {
+    writeln!(
+        out,
+        "<p>{} items {}, taking up {}.</p>",
+        list.total_items,
+        description,
+        human_file_size(list.total_size)
+    )
+}
+
+fn write_items(out: &mut String, list: &TopList, size_header: &str) -> Result {
+    if list.items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Title</th><th>Type</th><th class=\"number\">{}</th><th>Requested by</th><th>Last watched</th></tr>",
+        size_header
+    )?;
+    for item in &list.items {
+        write_item(out, item)?;
+    }
+
+    writeln!(out, "</table>")
+}
+
+fn write_item(out: &mut String, item: &ReportItem) -> Result {
+    writeln!(
+        out,
+        "<tr><td>{}</td><td>{}</td><td class=\"number\">{}</td><td>{}</td><td>{}</td></tr>",
+        escape(&item.title),
+        item.media_type,
+        human_file_size(item.size),
+        escape(item.requested_by.as_deref().unwrap_or("")),
+        item.last_watched
+            .map(|date| date.format("%d/%m/%Y").to_string())
+            .unwrap_or_else(|| "Never".to_string())
+    )
+}
+
+fn write_groups(out: &mut String, groups: &[Group], name_header: &str) -> Result {
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>{}</th><th class=\"number\">Items</th><th class=\"number\">Size</th></tr>",
+        name_header
+    )?;
+    for group in groups {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td></tr>",
+            escape(&group.name),
+            group.items,
+            human_file_size(group.size)
+        )?;
+    }
+
+    writeln!(out, "</table>")
+}
+
+fn write_requesters(out: &mut String, requesters: &[RequesterStats]) -> Result {
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Requester</th><th class=\"number\">Requests</th><th class=\"number\">Size</th><th class=\"number\">Watched by anyone</th><th class=\"number\">Watched by requester</th><th>Quota</th></tr>"
+    )?;
+    for stats in requesters {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td><td class=\"number\">{:.0}%</td><td class=\"number\">{:.0}%</td><td>{}</td></tr>",
+            escape(&stats.name),
+            stats.requests,
+            human_file_size(stats.size),
+            stats.watched_share * 100.0,
+            stats.watched_by_requester_share * 100.0,
+            quota_description(stats)
+        )?;
+    }
+
+    writeln!(out, "</table>")
+}
+
+fn write_not_requested(out: &mut String, not_requested: &Group) -> Result {
+    if not_requested.items == 0 {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "<p>{} items without a request take up {}.</p>",
+        not_requested.items,
+        human_file_size(not_requested.size)
+    )
+}
+
+fn quota_description(stats: &RequesterStats) -> String {
+    match (stats.quota, stats.over_quota) {
+        (Some(quota), true) => format!("<strong>Over</strong> ({})", human_file_size(quota)),
+        (Some(quota), false) => human_file_size(quota),
+        (None, _) => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,160 @@
+use std::fmt::{Result, Write};
+
+use super::{Group, ReportItem, TopList, WasteReport};
+use crate::requesters::RequesterStats;
+use crate::utils::human_file_size;
+
+pub fn render(report: &WasteReport) -> std::result::Result<String, std::fmt::Error> {
+    let mut out = String::new();
+
+    writeln!(out, "# Library waste report\n")?;
+    writeln!(
+        out,
+        "Generated on {}, covering {} items taking up {}.",
+        report.generated_at.format("%d/%m/%Y"),
+        report.total_items,
+        human_file_size(report.total_size)
+    )?;
+    if report.gather_errors > 0 {
+        writeln!(
+            out,
+            "{} items could not be gathered and are left out.",
+            report.gather_errors
+        )?;
+    }
+
+    writeln!(out, "\n## Largest never watched items\n")?;
+    write_summary(&mut out, &report.never_watched, "have never been watched")?;
+    write_items(&mut out, &report.never_watched, "Size")?;
+
+    writeln!(out, "\n## Space by requester\n")?;
+    write_requesters(&mut out, &report.by_requester)?;
+    write_not_requested(&mut out, &report.not_requested)?;
+
+    writeln!(out, "\n## Space by last watched\n")?;
+    write_groups(&mut out, &report.by_last_watched, "Last watched")?;
+
+    writeln!(out, "\n## 4K duplicates\n")?;
+    write_summary(
+        &mut out,
+        &report.duplicates_4k,
+        "are in both a standard and a 4K instance, with the 4K copies",
+    )?;
+    write_items(&mut out, &report.duplicates_4k, "4K size")?;
+
+    writeln!(out, "\n## Ended series without viewers in a year\n")?;
+    write_summary(
+        &mut out,
+        &report.stale_series,
+        "have ended and not been watched in a year",
+    )?;
+    write_items(&mut out, &report.stale_series, "Size")?;
+
+    Ok(out)
+}
+
+fn write_summary(out: &mut String, list: &TopList, description: &str) -> Result {
+    writeln!(
+        out,
+        "{} items {}, taking up {}.\n",
+        list.total_items,
+        description,
+        human_file_size(list.total_size)
+    )
+}
+
+fn write_items(out: &mut String, list: &TopList, size_header: &str) -> Result {
+    if list.items.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "| Title | Type | {} | Requested by | Last watched |",
+        size_header
+    )?;
+    writeln!(out, "| --- | --- | --: | --- | --- |")?;
+    for item in &list.items {
+        write_item(out, item)?;
+    }
+
+    Ok(())
+}
+
+fn write_item(out: &mut String, item: &ReportItem) -> Result {
+    writeln!(
+        out,
+        "| {} | {} | {} | {} | {} |",
+        escape(&item.title),
+        item.media_type,
+        human_file_size(item.size),
+        escape(item.requested_by.as_deref().unwrap_or("")),
+        item.last_watched
+            .map(|date| date.format("%d/%m/%Y").to_string())
+            .unwrap_or_else(|| "Never".to_string())
+    )
+}
+
+fn write_groups(out: &mut String, groups: &[Group], name_header: &str) -> Result {
+    writeln!(out, "| {} | Items | Size |", name_header)?;
+    writeln!(out, "| --- | --: | --: |")?;
+    for group in groups {
+        writeln!(
+            out,
+            "| {} | {} | {} |",
+            escape(&group.name),
+            group.items,
+            human_file_size(group.size)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_requesters(out: &mut String, requesters: &[RequesterStats]) -> Result {
+    writeln!(
+        out,
+        "| Requester | Requests | Size | Watched by anyone | Watched by requester | Quota |"
+    )?;
+    writeln!(out, "| --- | --: | --: | --: | --: | --- |")?;
+    for stats in requesters {
+        writeln!(
+            out,
+            "| {} | {} | {} | {:.0}% | {:.0}% | {} |",
+            escape(&stats.name),
+            stats.requests,
+            human_file_size(stats.size),
+            stats.watched_share * 100.0,
+            stats.watched_by_requester_share * 100.0,
+            quota_description(stats)
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_not_requested(out: &mut String, not_requested: &Group) -> Result {
+    if not_requested.items == 0 {
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "\n{} items without a request take up {}.",
+        not_requested.items,
+        human_file_size(not_requested.size)
+    )
+}
+
+fn quota_description(stats: &RequesterStats) -> String {
+    match (stats.quota, stats.over_quota) {
+        (Some(quota), true) => format!("**Over** ({})", human_file_size(quota)),
+        (Some(quota), false) => human_file_size(quota),
+        (None, _) => String::new(),
+    }
+}
+
+/// Keeps titles from breaking the tables.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
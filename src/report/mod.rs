@@ -0,0 +1,215 @@
+mod html;
+mod markdown;
+
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use color_eyre::Result;
+use itertools::Itertools;
+use std::fs;
+
+use crate::{
+    arguments::Arguments,
+    gather,
+    media_item::CompleteMediaItem,
+    requesters::{self, RequesterStats},
+    shared::MediaType,
+};
+
+/// The age buckets for when an item was last watched, as (upper limit in days, label).
+const WATCHED_BUCKETS: [(i64, &str); 5] = [
+    (30, "Within 30 days"),
+    (90, "30 to 90 days ago"),
+    (180, "90 to 180 days ago"),
+    (365, "180 to 365 days ago"),
+    (i64::MAX, "Over a year ago"),
+];
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    #[value(alias = "md")]
+    Markdown,
+    Html,
+}
+
+#[derive(Debug)]
+struct ReportItem {
+    title: String,
+    media_type: MediaType,
+    size: i64,
+    requested_by: Option<String>,
+    last_watched: Option<DateTime<Utc>>,
+}
+
+impl ReportItem {
+    fn from_item(item: &CompleteMediaItem, size: i64) -> Self {
+        Self {
+            title: item.title.clone(),
+            media_type: item.media_type,
+            size,
+            requested_by: item
+                .get_request()
+                .map(|request| request.requested_by.clone()),
+            last_watched: item.get_last_watched(),
+        }
+    }
+}
+
+/// A number of items and the space they take up, under some shared label.
+#[derive(Debug)]
+struct Group {
+    name: String,
+    items: usize,
+    size: i64,
+}
+
+/// A list of items where only the largest are shown, along with the totals for all of them.
+#[derive(Debug)]
+struct TopList {
+    items: Vec<ReportItem>,
+    total_items: usize,
+    total_size: i64,
+}
+
+impl TopList {
+    fn new(mut items: Vec<ReportItem>, top: usize) -> Self {
+        let total_items = items.len();
+        let total_size = items.iter().map(|item| item.size).sum();
+
+        items.sort_by_key(|item| -item.size);
+        items.truncate(top);
+
+        Self {
+            items,
+            total_items,
+            total_size,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct WasteReport {
+    generated_at: DateTime<Local>,
+    total_items: usize,
+    total_size: i64,
+    gather_errors: usize,
+    never_watched: TopList,
+    by_requester: Vec<RequesterStats>,
+    not_requested: Group,
+    by_last_watched: Vec<Group>,
+    duplicates_4k: TopList,
+    stale_series: TopList,
+}
+
+impl WasteReport {
+    fn new(items: &[CompleteMediaItem], top: usize, gather_errors: usize) -> Self {
+        let never_watched = items
+            .iter()
+            .filter(|item| item.get_last_watched().is_none())
+            .map(|item| ReportItem::from_item(item, item.get_disk_size()))
+            .collect();
+
+        let duplicates_4k = items
+            .iter()
+            .filter_map(|item| {
+                item.get_4k_duplicate_size()
+                    .map(|size| ReportItem::from_item(item, size))
+            })
+            .collect();
+
+        let stale_series = items
+            .iter()
+            .filter(|item| item.get_arr_data().is_some_and(|data| data.has_ended()))
+            .filter(|item| days_since_watched(item).is_none_or(|days| days >= 365))
+            .map(|item| ReportItem::from_item(item, item.get_disk_size()))
+            .collect();
+
+        let not_requested = items
+            .iter()
+            .filter(|item| item.get_request().is_none())
+            .collect_vec();
+
+        Self {
+            generated_at: Local::now(),
+            total_items: items.len(),
+            total_size: items.iter().map(|item| item.get_disk_size()).sum(),
+            gather_errors,
+            never_watched: TopList::new(never_watched, top),
+            by_requester: requesters::requester_stats(items),
+            not_requested: Group {
+                name: "Not requested".to_string(),
+                items: not_requested.len(),
+                size: not_requested.iter().map(|item| item.get_disk_size()).sum(),
+            },
+            by_last_watched: group_by_last_watched(items),
+            duplicates_4k: TopList::new(duplicates_4k, top),
+            stale_series: TopList::new(stale_series, top),
+        }
+    }
+}
+
+/// Gathers the data and writes the report to the chosen file, or to stdout.
+pub async fn run() -> Result<()> {
+    let args = Arguments::get_args();
+
+    eprintln!("Gathering all required data from your services.\nDepending on the amount of data and your connection speed, this could take a while...");
+    let (items, errors) = gather::gather_items(args.all_media).await?;
+
+    if !errors.is_empty() {
+        eprintln!(
+            "Left out {} items that could not be gathered:",
+            errors.len()
+        );
+        errors.iter().for_each(|err| eprintln!("- {}", err));
+    }
+
+    let report = WasteReport::new(&items, args.report.top, errors.len());
+    let rendered = match args.report.format {
+        ReportFormat::Markdown => markdown::render(&report)?,
+        ReportFormat::Html => html::render(&report)?,
+    };
+
+    match args.report.output {
+        Some(ref path) => {
+            fs::write(path, rendered)?;
+            eprintln!("Wrote the report to {}.", path);
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+fn days_since_watched(item: &CompleteMediaItem) -> Option<i64> {
+    item.get_last_watched()
+        .map(|date| Utc::now().signed_duration_since(date).num_days())
+}
+
+fn group_by_last_watched(items: &[CompleteMediaItem]) -> Vec<Group> {
+    let mut groups = vec![Group {
+        name: "Never".to_string(),
+        items: 0,
+        size: 0,
+    }];
+    groups.extend(WATCHED_BUCKETS.iter().map(|(_, name)| Group {
+        name: name.to_string(),
+        items: 0,
+        size: 0,
+    }));
+
+    for item in items {
+        let index = match days_since_watched(item) {
+            None => 0,
+            Some(days) => {
+                1 + WATCHED_BUCKETS
+                    .iter()
+                    .position(|(limit, _)| days < *limit)
+                    .unwrap_or(WATCHED_BUCKETS.len() - 1)
+            }
+        };
+
+        groups[index].items += 1;
+        groups[index].size += item.get_disk_size();
+    }
+
+    groups
+}
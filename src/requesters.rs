@@ -0,0 +1,111 @@
+use itertools::Itertools;
+use serde::Serialize;
+use std::{borrow::Borrow, collections::HashMap};
+
+use crate::{config::Config, media_item::CompleteMediaItem, overseerr::MediaRequest};
+
+/// Who made a request. Overseerr's user id when it is known, as display names can change and
+/// can be shared by several users.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RequesterKey {
+    Overseerr(u32),
+    Name(String),
+}
+
+impl RequesterKey {
+    pub fn of(request: &MediaRequest) -> Self {
+        match request.requester.overseerr_id {
+            Some(id) => Self::Overseerr(id),
+            None => Self::Name(request.requested_by.to_lowercase()),
+        }
+    }
+
+    pub fn overseerr_id(&self) -> Option<u32> {
+        match self {
+            Self::Overseerr(id) => Some(*id),
+            Self::Name(_) => None,
+        }
+    }
+}
+
+/// How much a single requester has asked for of the given items, and how much of it was watched.
+#[derive(Debug, Serialize)]
+pub struct RequesterStats {
+    pub name: String,
+    pub overseerr_id: Option<u32>,
+    pub requests: usize,
+    pub size: i64,
+    /// The share of the requests watched by anyone at all.
+    pub watched_share: f64,
+    /// The share of the requests watched by the requester themselves.
+    pub watched_by_requester_share: f64,
+    /// The space taken by all gathered requests of the requester, which the quota is checked
+    /// against.
+    pub quota_used: i64,
+    pub quota: Option<i64>,
+    pub over_quota: bool,
+}
+
+/// Stores on every item how much space all of its requester's items take, so the quotas are
+/// measured against the whole library however the items are filtered afterwards.
+pub fn record_usage(items: &mut [CompleteMediaItem]) {
+    let mut usage: HashMap<RequesterKey, i64> = HashMap::new();
+    for item in items.iter() {
+        if let Some(request) = item.get_request() {
+            *usage.entry(RequesterKey::of(request)).or_default() += item.get_disk_size();
+        }
+    }
+
+    for item in items.iter_mut() {
+        let used = item
+            .get_request()
+            .and_then(|request| usage.get(&RequesterKey::of(request)))
+            .copied();
+        if let Some(used) = used {
+            item.set_requester_usage(used);
+        }
+    }
+}
+
+/// Aggregates the requested items per requester, with the largest requesters first.
+pub fn requester_stats<T: Borrow<CompleteMediaItem>>(items: &[T]) -> Vec<RequesterStats> {
+    let config = Config::global();
+
+    items
+        .iter()
+        .map(|item| item.borrow())
+        .filter_map(|item| item.get_request().map(|request| (request, item)))
+        .into_group_map_by(|(request, _)| RequesterKey::of(request))
+        .into_values()
+        .map(|requests| {
+            let (request, item) = requests[0];
+            let count = requests.len();
+            let size = requests.iter().map(|(_, item)| item.get_disk_size()).sum();
+            let watched = requests
+                .iter()
+                .filter(|(_, item)| item.get_last_watched().is_some())
+                .count();
+            let watched_by_requester = requests
+                .iter()
+                .filter(|(_, item)| item.is_watched_by_requester())
+                .count();
+            let quota = config
+                .requester_quota
+                .as_ref()
+                .and_then(|quota| quota.quota_for(&request.requester));
+
+            RequesterStats {
+                name: request.requested_by.clone(),
+                overseerr_id: request.requester.overseerr_id,
+                requests: count,
+                size,
+                watched_share: watched as f64 / count as f64,
+                watched_by_requester_share: watched_by_requester as f64 / count as f64,
+                quota_used: item.get_requester_usage(),
+                quota,
+                over_quota: item.is_requester_over_quota(),
+            }
+        })
+        .sorted_by_key(|stats| -stats.size)
+        .collect()
+}
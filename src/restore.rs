@@ -0,0 +1,111 @@
+use color_eyre::{eyre::eyre, Report, Result};
+use dialoguer::MultiSelect;
+
+use crate::{
+    arguments::Arguments,
+    arr, cache,
+    config::Config,
+    deletion::{DeletedItem, DeletionPlan},
+    overseerr,
+};
+
+/// Lets the user pick items from the audit log and adds them back to the instances they were
+/// removed from, searching for them again. With `--with-request` the Overseerr request is made
+/// again as well, on behalf of the user who made it.
+pub async fn run() -> Result<()> {
+    if DeletionPlan::load()?.is_some() {
+        return Err(eyre!("An earlier deletion was interrupted before it finished. Please finish it with \"media-cleaner resume\" first."));
+    }
+
+    let mut deleted = DeletedItem::load_all()?;
+    if deleted.is_empty() {
+        println!("There are no deleted items to restore.");
+        return Ok(());
+    }
+
+    // Show the most recent deletions first.
+    deleted.reverse();
+
+    let chosen: Vec<usize> = MultiSelect::new()
+        .with_prompt("Choose what media to restore (SPACE to select, ENTER to confirm selection)")
+        .max_length(Config::global().items_shown)
+        .items(&deleted)
+        .interact()?;
+
+    if chosen.is_empty() {
+        println!("No items selected. Exiting...");
+        return Ok(());
+    }
+
+    let with_request = Arguments::get_args().with_request;
+    let mut errors = Vec::new();
+    let mut restored = 0;
+    for index in chosen {
+        let item = &mut deleted[index];
+        errors.append(&mut restore_item(item, with_request).await);
+
+        if is_restored(item, with_request) {
+            println!("Restored {}.", item.item.title);
+            restored += 1;
+        }
+    }
+
+    // Anything that was not restored fully stays in the log, to try again later.
+    deleted.retain(|item| !is_restored(item, with_request));
+    deleted.reverse();
+    DeletedItem::save_all(&deleted)?;
+
+    // The cached *arr data does not contain the restored items yet.
+    cache::clear()?;
+
+    println!("Restored {} items.", restored);
+    for (title, err) in errors.iter() {
+        println!("Got the following error while restoring {}: {}", title, err);
+    }
+
+    Ok(())
+}
+
+/// Whether nothing is left to restore of the item. Without `--with-request` its request is
+/// not restored, so it is not waited on either.
+fn is_restored(item: &DeletedItem, with_request: bool) -> bool {
+    item.instances.is_empty() && (!with_request || item.request.is_none())
+}
+
+/// Adds the item back to every instance it is still missing from, and makes the request again
+/// once it is back everywhere. Whatever was restored is taken out of the item.
+async fn restore_item(item: &mut DeletedItem, with_request: bool) -> Vec<(String, Report)> {
+    let mut errors = Vec::new();
+
+    let mut missing = Vec::new();
+    for instance in item.instances.drain(..) {
+        if let Err(err) = arr::restore_data(&instance).await {
+            errors.push((
+                item.item.title.clone(),
+                eyre!("{} Could not add it to {}.", err, instance.instance_name()),
+            ));
+            missing.push(instance);
+        }
+    }
+    item.instances = missing;
+
+    if !with_request || !item.instances.is_empty() {
+        return errors;
+    }
+
+    if let Some(ref request) = item.request {
+        match overseerr::restore_request(request).await {
+            Ok(()) => item.request = None,
+            Err(err) => errors.push((
+                item.item.title.clone(),
+                eyre!(
+                    "{} Could not make the request for {} again.",
+                    err,
+                    request.requested_by
+                ),
+            )),
+        }
+    }
+
+    errors
+}
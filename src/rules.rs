@@ -0,0 +1,38 @@
+use chrono::Utc;
+
+use crate::{config::Rules, media_item::CompleteMediaItem};
+
+/// Whether the item should be cleaned up according to the rules. Every configured rule has to
+/// match, while rules that do not apply to the item (e.g. a request age without a request) are
+/// ignored.
+pub fn matches(item: &CompleteMediaItem, rules: &Rules) -> bool {
+    if let Some(media_type) = rules.media_type {
+        if item.media_type != media_type {
+            return false;
+        }
+    }
+
+    if let (Some(min_age), Some(age)) = (rules.min_request_age_days, item.get_request_age_days()) {
+        if age < min_age {
+            return false;
+        }
+    }
+
+    if let (Some(days), Some(last_watched)) = (rules.unwatched_days, item.get_last_watched()) {
+        if Utc::now().signed_duration_since(last_watched).num_days() < days {
+            return false;
+        }
+    }
+
+    if let Some(min_size) = rules.min_size_gb {
+        if (item.get_disk_size() as f64) < min_size * 1000000000.0 {
+            return false;
+        }
+    }
+
+    if rules.only_fulfilled && !item.is_fulfilled() {
+        return false;
+    }
+
+    true
+}
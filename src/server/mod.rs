@@ -0,0 +1,362 @@
+mod views;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use color_eyre::Result;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    mem,
+    net::SocketAddr,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use self::views::{CandidatesView, DeletionRun, HealthView, ItemView, StartedRunView};
+use crate::{
+    arguments::{Arguments, Sort},
+    cache,
+    config::{default_server_address, Config, Rules},
+    deletion::{self, DeletionPlan, DeletionResult},
+    gather,
+    media_item::CompleteMediaItem,
+    metrics,
+    notifications::{ErrorSummary, RunMode},
+    requesters::{self, RequesterStats},
+    rules,
+    shared::{MediaType, SortingOption},
+    utils::shutdown_signal,
+};
+
+#[derive(Debug, Default)]
+struct ServerState {
+    items: Vec<CompleteMediaItem>,
+    gather_errors: Vec<String>,
+    gathering: bool,
+    gathered_at: Option<DateTime<Utc>>,
+    runs: Vec<DeletionRun>,
+}
+
+impl ServerState {
+    fn is_deleting(&self) -> bool {
+        self.runs
+            .last()
+            .is_some_and(|run| run.finished_at.is_none())
+    }
+
+    fn run_mut(&mut self, id: usize) -> Option<&mut DeletionRun> {
+        self.runs.iter_mut().find(|run| run.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct SharedState(Arc<Mutex<ServerState>>);
+
+impl SharedState {
+    fn lock(&self) -> MutexGuard<'_, ServerState> {
+        self.0.lock().expect("Server state lock has been poisoned.")
+    }
+}
+
+/// An error returned from the API, sent as `{"error": "..."}` with the given status code.
+struct ApiError(StatusCode, &'static str);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// The filters and sorting accepted by `GET /candidates`. The filters work the same as the daemon
+/// rules, and `sort` takes the same values as `--sort`, e.g. `size-asc`.
+#[derive(Debug, Deserialize)]
+struct CandidatesQuery {
+    sort: Option<String>,
+    media_type: Option<MediaType>,
+    min_request_age_days: Option<i64>,
+    unwatched_days: Option<i64>,
+    min_size_gb: Option<f64>,
+    #[serde(default)]
+    only_fulfilled: bool,
+    requested_by: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteRequest {
+    ids: Vec<String>,
+}
+
+/// Serves the web UI and the JSON API until the process is asked to stop.
+pub async fn run() -> Result<()> {
+    let address: SocketAddr = match Config::global().server {
+        Some(ref server) => server.address.parse()?,
+        None => default_server_address().parse()?,
+    };
+
+    metrics::register();
+
+    let state = SharedState::default();
+    start_gathering(&state);
+
+    let app = Router::new()
+        .route("/", get(index))
+        .route("/health", get(health))
+        .route("/candidates", get(candidates))
+        .route("/requesters", get(requester_stats))
+        .route("/refresh", post(refresh))
+        .route("/delete", post(delete))
+        .route("/runs", get(runs))
+        .route("/runs/:id", get(run_status))
+        .route("/metrics", get(metrics::handler))
+        .with_state(state);
+
+    println!("Serving the web UI at http://{}", address);
+    axum::Server::bind(&address)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    Ok(())
+}
+
+/// Gathers the items in the background, unless that is already happening.
+fn start_gathering(state: &SharedState) -> bool {
+    {
+        let mut guard = state.lock();
+        if guard.gathering {
+            return false;
+        }
+        guard.gathering = true;
+    }
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        let result = gather::gather_items(Arguments::get_args().all_media).await;
+
+        let mut guard = state.lock();
+        guard.gathering = false;
+        guard.gathered_at = Some(Utc::now());
+        match result {
+            Ok((items, errors)) => {
+                guard.items = items;
+                guard.gather_errors = errors.iter().map(|err| err.to_string()).collect();
+            }
+            Err(err) => guard.gather_errors = vec![err.to_string()],
+        }
+    });
+
+    true
+}
+
+async fn index() -> Html<&'static str> {
+    Html(include_str!("index.html"))
+}
+
+async fn health(State(state): State<SharedState>) -> Json<HealthView> {
+    let guard = state.lock();
+
+    Json(HealthView {
+        status: "ok",
+        gathering: guard.gathering,
+        gathered_at: guard.gathered_at,
+        candidates: guard.items.len(),
+        deleting: guard.is_deleting(),
+    })
+}
+
+async fn candidates(
+    State(state): State<SharedState>,
+    Query(query): Query<CandidatesQuery>,
+) -> Result<Response, ApiError> {
+    let sort = match query.sort {
+        Some(ref sort) => Sort::from_str(sort, false)
+            .map(SortingOption::from)
+            .map_err(|_| ApiError(StatusCode::BAD_REQUEST, "Not a valid sorting option."))?,
+        None => SortingOption::default(),
+    };
+    let rules = Rules {
+        media_type: query.media_type,
+        min_request_age_days: query.min_request_age_days,
+        unwatched_days: query.unwatched_days,
+        min_size_gb: query.min_size_gb,
+        only_fulfilled: query.only_fulfilled,
+    };
+
+    let guard = state.lock();
+    let mut items = guard
+        .items
+        .iter()
+        .filter(|item| rules::matches(item, &rules))
+        .filter(|item| match query.requested_by {
+            Some(ref name) => item
+                .get_request()
+                .is_some_and(|request| request.requester.has_name(name)),
+            None => true,
+        })
+        .collect_vec();
+    gather::sort_items(&mut items, &sort);
+
+    Ok(Json(CandidatesView {
+        gathering: guard.gathering,
+        gathered_at: guard.gathered_at,
+        errors: &guard.gather_errors,
+        items: items.into_iter().map(ItemView::from_item).collect(),
+    })
+    .into_response())
+}
+
+async fn requester_stats(State(state): State<SharedState>) -> Json<Vec<RequesterStats>> {
+    Json(requesters::requester_stats(&state.lock().items))
+}
+
+/// Checks the bearer token of a request that changes anything, when the config has one.
+fn authorize(headers: &HeaderMap) -> Result<(), ApiError> {
+    let config = Config::global();
+    let token = match config
+        .server
+        .as_ref()
+        .and_then(|server| server.token.as_ref())
+    {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+
+    let given = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if given != Some(token.as_str()) {
+        return Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "A valid token is needed for this.",
+        ));
+    }
+
+    Ok(())
+}
+
+async fn refresh(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    authorize(&headers)?;
+
+    if state.lock().is_deleting() {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            "Please wait for the current gathering or deletion to finish.",
+        ));
+    }
+
+    if cache::clear().is_err() {
+        return Err(ApiError(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to clear the cache.",
+        ));
+    }
+
+    if !start_gathering(&state) {
+        return Err(ApiError(
+            StatusCode::CONFLICT,
+            "Please wait for the current gathering or deletion to finish.",
+        ));
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn delete(
+    State(state): State<SharedState>,
+    headers: HeaderMap,
+    Json(request): Json<DeleteRequest>,
+) -> Result<(StatusCode, Json<StartedRunView>), ApiError> {
+    authorize(&headers)?;
+
+    let (id, chosen) = {
+        let mut guard = state.lock();
+        if guard.gathering || guard.is_deleting() {
+            return Err(ApiError(
+                StatusCode::CONFLICT,
+                "Please wait for the current gathering or deletion to finish.",
+            ));
+        }
+
+        if !matches!(DeletionPlan::load(), Ok(None)) {
+            return Err(ApiError(
+                StatusCode::CONFLICT,
+                "An earlier deletion was interrupted. Please finish it with the resume command first.",
+            ));
+        }
+
+        let (chosen, rest): (Vec<_>, Vec<_>) = mem::take(&mut guard.items)
+            .into_iter()
+            .partition(|item| request.ids.contains(&item.rating_key));
+        guard.items = rest;
+
+        if chosen.is_empty() {
+            return Err(ApiError(
+                StatusCode::BAD_REQUEST,
+                "None of the chosen items were found.",
+            ));
+        }
+
+        let id = guard.runs.len() + 1;
+        guard.runs.push(DeletionRun::new(id, chosen.len()));
+        (id, chosen)
+    };
+
+    tokio::spawn(run_deletion(state, id, chosen));
+
+    Ok((StatusCode::ACCEPTED, Json(StartedRunView { id })))
+}
+
+async fn run_deletion(state: SharedState, id: usize, chosen: Vec<CompleteMediaItem>) {
+    let result = deletion::delete_items(chosen, RunMode::Web, |result| {
+        if let Some(run) = state.lock().run_mut(id) {
+            run.deleted = result.deleted.clone();
+            run.errors = ErrorSummary::from_errors(&result.errors);
+            run.outcomes = result.outcomes.clone();
+        }
+    })
+    .await;
+
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => DeletionResult {
+            errors: vec![("the chosen items".to_string(), err)],
+            ..Default::default()
+        },
+    };
+
+    if let Some(run) = state.lock().run_mut(id) {
+        run.deleted = result.deleted;
+        run.errors = ErrorSummary::from_errors(&result.errors);
+        run.outcomes = result.outcomes;
+        run.notification_errors = ErrorSummary::from_errors(&result.notification_errors);
+        run.finished_at = Some(Utc::now());
+    }
+}
+
+async fn runs(State(state): State<SharedState>) -> Json<Vec<DeletionRun>> {
+    Json(state.lock().runs.clone())
+}
+
+async fn run_status(
+    State(state): State<SharedState>,
+    Path(id): Path<usize>,
+) -> Result<Json<DeletionRun>, ApiError> {
+    match state.lock().run_mut(id) {
+        Some(run) => Ok(Json(run.clone())),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            "No run with that id exists.",
+        )),
+    }
+}
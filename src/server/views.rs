@@ -0,0 +1,96 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::{
+    arr::ArrData,
+    deletion::ItemOutcome,
+    media_item::CompleteMediaItem,
+    notifications::{ErrorSummary, ItemSummary},
+    shared::MediaType,
+    tautulli::WatchHistory,
+};
+
+#[derive(Debug, Serialize)]
+pub struct HealthView {
+    pub status: &'static str,
+    pub gathering: bool,
+    pub gathered_at: Option<DateTime<Utc>>,
+    pub candidates: usize,
+    pub deleting: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CandidatesView<'a> {
+    pub gathering: bool,
+    pub gathered_at: Option<DateTime<Utc>>,
+    pub errors: &'a [String],
+    pub items: Vec<ItemView<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ItemView<'a> {
+    id: &'a str,
+    title: &'a str,
+    media_type: MediaType,
+    size: i64,
+    status_4k: &'a str,
+    requested_by: Option<&'a str>,
+    requested_date: Option<DateTime<Utc>>,
+    last_watched: Option<DateTime<Utc>>,
+    fulfilled: bool,
+    history: &'a WatchHistory,
+    arr_data: Option<&'a ArrData>,
+}
+
+impl<'a> ItemView<'a> {
+    pub fn from_item(item: &'a CompleteMediaItem) -> Self {
+        Self {
+            id: &item.rating_key,
+            title: &item.title,
+            media_type: item.media_type,
+            size: item.get_disk_size(),
+            status_4k: item.status_4k().trim(),
+            requested_by: item
+                .get_request()
+                .map(|request| request.requested_by.as_str()),
+            requested_date: item.get_requested_date(),
+            last_watched: item.get_last_watched(),
+            fulfilled: item.is_fulfilled(),
+            history: item.get_history(),
+            arr_data: item.get_arr_data(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StartedRunView {
+    pub id: usize,
+}
+
+/// A deletion started through the API, which can be followed until it has finished.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeletionRun {
+    pub id: usize,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub total: usize,
+    pub deleted: Vec<ItemSummary>,
+    pub errors: Vec<ErrorSummary>,
+    pub outcomes: Vec<ItemOutcome>,
+    pub notification_errors: Vec<ErrorSummary>,
+}
+
+impl DeletionRun {
+    pub fn new(id: usize, total: usize) -> Self {
+        Self {
+            id,
+            started_at: Utc::now(),
+            finished_at: None,
+            total,
+            deleted: Vec::new(),
+            errors: Vec::new(),
+            outcomes: Vec::new(),
+            notification_errors: Vec::new(),
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use color_eyre::{eyre::eyre, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase")]
 pub enum MediaType {
     Movie,
@@ -26,11 +26,7 @@ pub enum Order {
 
 impl PartialEq for Order {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Order::Asc, Order::Asc) => true,
-            (Order::Desc, Order::Desc) => true,
-            _ => false,
-        }
+        matches!((self, other), (Order::Asc, Order::Asc) | (Order::Desc, Order::Desc))
     }
 }
 
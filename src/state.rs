@@ -0,0 +1,58 @@
+use color_eyre::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::config::Config;
+
+/// The directory where state that has to survive between runs is kept.
+/// Uses `data_dir` from the config if set, otherwise the platform's local data directory.
+pub fn data_dir() -> Result<PathBuf> {
+    let dir = match Config::global().data_dir {
+        Some(ref dir) => PathBuf::from(dir),
+        None => dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("media-cleaner"),
+    };
+
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Reads a JSON state file, returning `None` if it has not been written yet.
+pub fn load<T>(name: &str) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    let path = data_dir()?.join(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let reader = fs::File::open(path)?;
+    Ok(Some(serde_json::from_reader(reader)?))
+}
+
+/// Writes a JSON state file. The file is written next to the old one and then moved into place,
+/// so a crash never leaves a half written file behind.
+pub fn save<T>(name: &str, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    let dir = data_dir()?;
+    let temp_path = dir.join(format!("{}.tmp", name));
+
+    fs::write(&temp_path, serde_json::to_vec_pretty(value)?)?;
+    fs::rename(temp_path, dir.join(name))?;
+
+    Ok(())
+}
+
+/// Removes a JSON state file, if it exists.
+pub fn remove(name: &str) -> Result<()> {
+    let path = data_dir()?.join(name);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
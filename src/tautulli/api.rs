@@ -1,38 +1,40 @@
-use color_eyre::{eyre::eyre, Result};
+use color_eyre::Result;
 use serde::de::DeserializeOwned;
 
 use super::responses::ResponseObj;
-use crate::{
-    config::Config,
-    utils::{create_api_error_message, create_param_string},
-};
-
-trait Response<TypeParam> {
-    type TypeParam;
-}
+use crate::{cache, config::Config, error::ServiceError, http, utils::create_param_string};
 
 pub async fn get_obj<T>(command: &str, params: Option<Vec<(&str, &str)>>) -> Result<ResponseObj<T>>
 where
     T: DeserializeOwned,
 {
     let config = &Config::global().tautulli;
-    let client = reqwest::Client::new();
+    let client = http::client("tautulli", "tautulli", &config.requests);
 
     let cmd = command.to_string() + "&" + &create_param_string(params);
 
-    let url = format!(
-        "{}/api/v2?apikey={}&cmd={}",
-        config.url, config.api_key, cmd
-    );
+    let key = format!("{}/api/v2?cmd={}", config.url, cmd);
+    let body = cache::get_or_fetch("tautulli", &key, || async {
+        // Tautulli only takes the key as a parameter, so it is kept out of the errors instead.
+        let url = format!(
+            "{}/api/v2?apikey={}&cmd={}",
+            config.url, config.api_key, cmd
+        );
+
+        let response = client.send(client.get(&url)).await?;
 
-    let response = client.get(&url).send().await?;
+        if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
+            return Err(ServiceError::from_response("Tautulli", command, response)
+                .await
+                .into());
+        }
 
-    if !(response.status().as_u16() >= 200 && response.status().as_u16() < 300) {
-        let code = response.status().as_u16();
-        return Err(eyre!(create_api_error_message(code, &url, "Tautulli")));
-    }
+        client.text(command, response).await
+    })
+    .await?;
 
-    let response = response.json().await?;
+    let response = serde_json::from_str(&body)
+        .map_err(|err| ServiceError::parse("Tautulli", command, err, &body))?;
 
     Ok(response)
 }
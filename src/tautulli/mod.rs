@@ -2,43 +2,74 @@ mod api;
 mod responses;
 
 use chrono::prelude::*;
-use color_eyre::{owo_colors::OwoColorize, Result};
-use serde::de::DeserializeOwned;
-use std::{collections::BTreeMap, fmt::Display};
+use color_eyre::{eyre::eyre, owo_colors::OwoColorize, Result};
+use itertools::Itertools;
+use serde::Serialize;
+use std::{cmp::Reverse, collections::HashMap, fmt::Display};
 
-use self::responses::{History, HistoryItem, HistoryMovieItem};
-use crate::{shared::MediaType, tautulli::responses::ResponseObj};
+use self::responses::{History, HistoryItem, ResultType, ServerInfo, UserItem};
+use crate::{shared::MediaType, tautulli::responses::ResponseObj, users::UserIdentity};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
 pub enum WatchHistory {
     Movie(ItemWatches<UserMovieWatch>),
     TvShow(ItemWatches<UserEpisodeWatch>),
 }
 
 impl WatchHistory {
-    fn from_user_watches(
-        user_watches: BTreeMap<&String, &HistoryItem>,
-        media_type: &MediaType,
-        rating_key: &str,
-    ) -> Self {
+    pub fn last_watched(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Movie(watches) => watches.iter().map(|watch| watch.last_watched).max(),
+            Self::TvShow(watches) => watches.iter().map(|watch| watch.last_watched).max(),
+        }
+    }
+
+    /// Whether the user has watched the movie with at least `min_progress` percent complete.
+    /// For series they instead have to have reached `last_episode` (season, episode) with that
+    /// progress, so series without any known episode on disk are never considered watched.
+    pub fn watched_by(
+        &self,
+        user: &UserIdentity,
+        min_progress: u8,
+        last_episode: Option<(u32, u32)>,
+    ) -> bool {
+        match self {
+            Self::Movie(watches) => watches.iter().any(|watch| {
+                user.matches(watch.user_id, &watch.display_name) && watch.progress >= min_progress
+            }),
+            Self::TvShow(watches) => {
+                let last_episode = match last_episode {
+                    Some(last_episode) => last_episode,
+                    None => return false,
+                };
+
+                watches.iter().any(|watch| {
+                    let episode = (watch.season, watch.episode);
+                    user.matches(watch.user_id, &watch.display_name)
+                        && (episode > last_episode
+                            || (episode == last_episode && watch.progress >= min_progress))
+                })
+            }
+        }
+    }
+
+    fn from_watches(watches: Vec<&HistoryItem>, media_type: &MediaType, rating_key: &str) -> Self {
         match media_type {
-            MediaType::Movie => WatchHistory::create_movie_history(user_watches, rating_key),
-            MediaType::Tv => WatchHistory::create_tv_history(user_watches, rating_key),
+            MediaType::Movie => WatchHistory::create_movie_history(watches, rating_key),
+            MediaType::Tv => WatchHistory::create_tv_history(watches, rating_key),
         }
     }
 
-    fn create_movie_history(
-        user_watches: BTreeMap<&String, &HistoryItem>,
-        rating_key: &str,
-    ) -> Self {
-        let watches = user_watches
+    fn create_movie_history(watches: Vec<&HistoryItem>, rating_key: &str) -> Self {
+        let watches = watches
             .iter()
-            .map(|(user, movie_watch)| UserMovieWatch {
-                display_name: user.to_string(),
-                last_watched: unix_seconds_to_date(movie_watch.date).expect(&format!(
-                    "Failed to parse unix time for rating key {}",
-                    rating_key
-                )),
+            .map(|movie_watch| UserMovieWatch {
+                display_name: movie_watch.user.clone(),
+                user_id: movie_watch.user_id,
+                last_watched: unix_seconds_to_date(movie_watch.date).unwrap_or_else(|| {
+                    panic!("Failed to parse unix time for rating key {}", rating_key)
+                }),
                 progress: movie_watch.percent_complete,
             })
             .collect();
@@ -46,18 +77,18 @@ impl WatchHistory {
         WatchHistory::Movie(watches)
     }
 
-    fn create_tv_history(user_watches: BTreeMap<&String, &HistoryItem>, rating_key: &str) -> Self {
-        let watches = user_watches
+    fn create_tv_history(watches: Vec<&HistoryItem>, rating_key: &str) -> Self {
+        let watches = watches
             .iter()
-            .map(|(user, tv_watch)| UserEpisodeWatch {
-                display_name: user.to_string(),
-                last_watched: unix_seconds_to_date(tv_watch.date).expect(&format!(
-                    "Failed to parse unix time for rating key {}",
-                    rating_key
-                )),
+            .map(|tv_watch| UserEpisodeWatch {
+                display_name: tv_watch.user.clone(),
+                user_id: tv_watch.user_id,
+                last_watched: unix_seconds_to_date(tv_watch.date).unwrap_or_else(|| {
+                    panic!("Failed to parse unix time for rating key {}", rating_key)
+                }),
                 progress: tv_watch.percent_complete,
-                season: tv_watch.parent_media_index.unwrap(),
-                episode: tv_watch.media_index.unwrap(),
+                season: tv_watch.parent_media_index.unwrap_or_default(),
+                episode: tv_watch.media_index.unwrap_or_default(),
             })
             .collect();
 
@@ -67,20 +98,29 @@ impl WatchHistory {
 
 impl Display for WatchHistory {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Only the latest watch of every user is shown, the watches are sorted newest first.
         match self {
-            Self::Movie(watches) => write_watches(f, watches),
-            Self::TvShow(watches) => write_watches(f, watches),
+            Self::Movie(watches) => {
+                write_watches(f, watches.iter().unique_by(|watch| &watch.display_name))
+            }
+            Self::TvShow(watches) => {
+                write_watches(f, watches.iter().unique_by(|watch| &watch.display_name))
+            }
         }
     }
 }
 
-fn write_watches<T>(f: &mut std::fmt::Formatter, watches: &ItemWatches<T>) -> std::fmt::Result
+fn write_watches<'a, T>(
+    f: &mut std::fmt::Formatter,
+    watches: impl Iterator<Item = &'a T>,
+) -> std::fmt::Result
 where
-    T: Display,
+    T: Display + 'a,
 {
-    if watches.len() > 0 {
+    let mut watches = watches.peekable();
+    if watches.peek().is_some() {
         write!(f, "Watch history:")?;
-        for watch in watches.iter() {
+        for watch in watches {
             write!(f, "\n      * {}", watch)?;
         }
         Ok(())
@@ -91,9 +131,10 @@ where
 
 pub type ItemWatches<T> = Vec<T>;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct UserEpisodeWatch {
     display_name: String,
+    user_id: Option<u32>,
     last_watched: DateTime<Utc>,
     progress: u8,
     season: u32,
@@ -114,9 +155,10 @@ impl Display for UserEpisodeWatch {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct UserMovieWatch {
     display_name: String,
+    user_id: Option<u32>,
     last_watched: DateTime<Utc>,
     progress: u8,
 }
@@ -133,88 +175,104 @@ impl Display for UserMovieWatch {
     }
 }
 
-pub async fn get_item_watches(rating_key: &str, media_type: &MediaType) -> Result<WatchHistory> {
-    let history = get_item_history(rating_key, media_type).await?;
+/// The name and version of the Plex server Tautulli is connected to.
+pub async fn get_server_info() -> Result<(String, String)> {
+    let info: ResponseObj<ServerInfo> = api::get_obj("get_server_info", None).await?;
 
-    let latest_user_history =
-        history
-            .iter()
-            .fold(BTreeMap::new(), |mut user_latest_watch, current_watch| {
-                user_latest_watch
-                    .entry(&current_watch.user)
-                    .and_modify(|entry: &mut &HistoryItem| {
-                        if entry.date < current_watch.date {
-                            *entry = current_watch;
-                        }
-                    })
-                    .or_insert(current_watch);
-
-                user_latest_watch
-            });
-
-    Ok(WatchHistory::from_user_watches(
-        latest_user_history,
-        media_type,
-        rating_key,
-    ))
+    if info.response.result == ResultType::Error {
+        return Err(eyre!(
+            "Tautulli could not get the server info: {}",
+            info.response.message.unwrap_or_default()
+        ));
+    }
+
+    Ok((info.response.data.pms_name, info.response.data.pms_version))
 }
 
-async fn get_item_history(rating_key: &str, media_type: &MediaType) -> Result<Vec<HistoryItem>> {
-    if let MediaType::Movie = media_type {
-        let history: Vec<HistoryMovieItem> = get_full_history(rating_key, "rating_key").await?;
-        Ok(movie_item_to_history_item(history))
-    } else {
-        let history: Vec<HistoryItem> =
-            get_full_history(rating_key, "grandparent_rating_key").await?;
-        Ok(history)
+pub async fn get_users() -> Result<Vec<UserIdentity>> {
+    let users: ResponseObj<Vec<UserItem>> = api::get_obj("get_users", None).await?;
+
+    Ok(users
+        .response
+        .data
+        .into_iter()
+        .map(|user| {
+            UserIdentity::new(
+                None,
+                Some(user.user_id),
+                vec![user.friendly_name, user.username, user.email],
+            )
+        })
+        .collect())
+}
+
+/// The watch history of the whole server, grouped by the rating key of each movie and series.
+#[derive(Debug, Default)]
+pub struct LibraryHistory {
+    movies: HashMap<String, Vec<HistoryItem>>,
+    series: HashMap<String, Vec<HistoryItem>>,
+}
+
+impl LibraryHistory {
+    /// Every watch of the item, newest first. All of them are kept, so rewatching an earlier
+    /// episode does not hide that the last one was watched.
+    pub fn item_watches(&self, rating_key: &str, media_type: &MediaType) -> WatchHistory {
+        let history = match media_type {
+            MediaType::Movie => self.movies.get(rating_key),
+            MediaType::Tv => self.series.get(rating_key),
+        };
+
+        let watches = history
+            .into_iter()
+            .flatten()
+            .sorted_by_key(|watch| Reverse(watch.date))
+            .collect_vec();
+
+        WatchHistory::from_watches(watches, media_type, rating_key)
     }
 }
 
-async fn get_full_history<T>(rating_key: &str, rating_key_kind: &str) -> Result<Vec<T>>
-where
-    T: DeserializeOwned,
-{
-    let length = 1000;
+/// Fetches the full watch history in large pages, instead of asking for every item separately.
+pub async fn get_library_history() -> Result<LibraryHistory> {
+    let length = 5000;
     let length_string = length.to_string();
-    let mut history: Vec<T> = Vec::new();
-    let mut page = 0;
+    let mut history = LibraryHistory::default();
+    let mut start = 0;
     loop {
-        let page_string = page.to_string();
-        let params = vec![
-            (rating_key_kind, rating_key),
-            ("length", &length_string),
-            ("start", &page_string),
-        ];
-        let mut history_page: ResponseObj<History<T>> =
+        let start_string = start.to_string();
+        let params = vec![("length", length_string.as_str()), ("start", &start_string)];
+        let history_page: ResponseObj<History<HistoryItem>> =
             api::get_obj("get_history", Some(params)).await?;
 
-        if history_page.response.data.data.len() < length {
-            history.append(&mut history_page.response.data.data);
+        let page = history_page.response.data.data;
+        let page_length = page.len();
+        for item in page {
+            let key = match item.media_type.as_str() {
+                "movie" => item
+                    .rating_key
+                    .clone()
+                    .map(|key| (&mut history.movies, key)),
+                "episode" => item
+                    .grandparent_rating_key
+                    .clone()
+                    .map(|key| (&mut history.series, key)),
+                _ => None,
+            };
+
+            if let Some((items, key)) = key {
+                items.entry(key).or_default().push(item);
+            }
+        }
+
+        if page_length < length {
             break;
-        } else {
-            history.append(&mut history_page.response.data.data);
-            page += 1;
         }
+        start += length;
     }
 
     Ok(history)
 }
 
-fn movie_item_to_history_item(history: Vec<HistoryMovieItem>) -> Vec<HistoryItem> {
-    history
-        .into_iter()
-        .map(|item| HistoryItem {
-            user: item.user,
-            date: item.date,
-            duration: item.duration,
-            percent_complete: item.percent_complete,
-            media_index: None,
-            parent_media_index: None,
-        })
-        .collect()
-}
-
 fn unix_seconds_to_date(unix_seconds: i64) -> Option<DateTime<Utc>> {
-    let naive_date = NaiveDateTime::from_timestamp_millis(unix_seconds * 1000).unwrap();
-    Some(DateTime::from_utc(naive_date, Utc))
+    Utc.timestamp_opt(unix_seconds, 0).single()
 }
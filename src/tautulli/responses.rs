@@ -1,18 +1,5 @@
-use serde::Deserialize;
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct ResponseArr<T> {
-    pub response: ResponseInternalArr<T>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct ResponseInternalArr<T> {
-    pub message: Option<String>,
-    pub result: ResultType,
-    pub data: Vec<T>,
-}
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -28,7 +15,7 @@ pub struct ResponseInternalObj<T> {
     pub data: T,
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ResultType {
     Success,
@@ -38,9 +25,6 @@ pub enum ResultType {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct History<T> {
-    pub draw: u32,
-    pub records_total: u32,
-    pub records_filtered: u32,
     pub data: Vec<T>,
 }
 
@@ -48,18 +32,51 @@ pub struct History<T> {
 #[serde(rename_all = "snake_case")]
 pub struct HistoryItem {
     pub user: String,
+    pub user_id: Option<u32>,
     pub date: i64,
-    pub duration: u64,
     pub percent_complete: u8,
+    pub media_type: String,
+    #[serde(deserialize_with = "deserialize_optional_key")]
+    pub rating_key: Option<String>,
+    #[serde(deserialize_with = "deserialize_optional_key")]
+    pub grandparent_rating_key: Option<String>,
+    #[serde(deserialize_with = "deserialize_optional_index")]
     pub media_index: Option<u32>,
+    #[serde(deserialize_with = "deserialize_optional_index")]
     pub parent_media_index: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "snake_case")]
-pub struct HistoryMovieItem {
-    pub date: i64,
-    pub duration: u64,
-    pub percent_complete: u8,
-    pub user: String,
+pub struct UserItem {
+    pub user_id: u32,
+    pub username: Option<String>,
+    pub friendly_name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Tautulli sends keys as numbers, numeric strings or empty strings depending on the media type.
+fn deserialize_optional_key<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(number) => Some(number.to_string()),
+        Value::String(string) if !string.is_empty() => Some(string),
+        _ => None,
+    })
+}
+
+fn deserialize_optional_index<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_optional_key(deserializer)?.and_then(|index| index.parse().ok()))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct ServerInfo {
+    pub pms_name: String,
+    pub pms_version: String,
 }
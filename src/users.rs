@@ -0,0 +1,143 @@
+use itertools::Itertools;
+use std::sync::{Arc, RwLock};
+use tokio::join;
+
+use crate::{overseerr, plex, tautulli};
+
+static INSTANCE: RwLock<Option<Arc<UserRegistry>>> = RwLock::new(None);
+
+/// The different names and ids a single person can show up under across the services.
+///
+/// Overseerr knows a user by display name, username and email, while Tautulli reports the Plex
+/// friendly name and the Plex user id. Matching on the Plex id is preferred, with the names as a
+/// (case insensitive) fallback for users that have not linked their Plex account.
+#[derive(Debug, Clone, Default)]
+pub struct UserIdentity {
+    pub overseerr_id: Option<u32>,
+    pub plex_id: Option<u32>,
+    pub names: Vec<String>,
+}
+
+impl UserIdentity {
+    pub fn new(
+        overseerr_id: Option<u32>,
+        plex_id: Option<u32>,
+        names: Vec<Option<String>>,
+    ) -> Self {
+        let names = names
+            .into_iter()
+            .flatten()
+            .filter(|name| !name.is_empty())
+            .unique_by(|name| name.to_lowercase())
+            .collect();
+
+        Self {
+            overseerr_id,
+            plex_id,
+            names,
+        }
+    }
+
+    pub fn matches(&self, plex_id: Option<u32>, name: &str) -> bool {
+        if let (Some(own_id), Some(other_id)) = (self.plex_id, plex_id) {
+            return own_id == other_id;
+        }
+
+        self.has_name(name)
+    }
+
+    pub fn has_name(&self, name: &str) -> bool {
+        self.names.iter().any(|own| own.eq_ignore_ascii_case(name))
+    }
+
+    fn is_same_user(&self, other: &UserIdentity) -> bool {
+        if let (Some(own_id), Some(other_id)) = (self.overseerr_id, other.overseerr_id) {
+            return own_id == other_id;
+        }
+        if let (Some(own_id), Some(other_id)) = (self.plex_id, other.plex_id) {
+            return own_id == other_id;
+        }
+
+        other.names.iter().any(|name| self.has_name(name))
+    }
+
+    fn absorb(&mut self, other: UserIdentity) {
+        self.overseerr_id = self.overseerr_id.or(other.overseerr_id);
+        self.plex_id = self.plex_id.or(other.plex_id);
+
+        for name in other.names {
+            if !self.has_name(&name) {
+                self.names.push(name);
+            }
+        }
+    }
+}
+
+/// All known users, joined across Overseerr, Tautulli and Plex.
+#[derive(Debug, Default)]
+pub struct UserRegistry {
+    users: Vec<UserIdentity>,
+}
+
+impl UserRegistry {
+    pub fn global() -> Arc<UserRegistry> {
+        INSTANCE
+            .read()
+            .expect("User registry lock has been poisoned.")
+            .clone()
+            .expect("User registry has not been initialized.")
+    }
+
+    /// Fetches the users from all services, replacing the current registry. A service whose users
+    /// can't be fetched is left out with a warning, e.g. Plex refusing `/accounts` for a token
+    /// that is not the owner's. Users are then only matched by what the others know of them.
+    pub async fn load() {
+        let (overseerr_users, tautulli_users, plex_accounts) = join!(
+            overseerr::get_users(),
+            tautulli::get_users(),
+            plex::get_accounts()
+        );
+
+        let mut registry = UserRegistry::default();
+        for (service, users) in [
+            ("Overseerr", overseerr_users),
+            ("Tautulli", tautulli_users),
+            ("Plex", plex_accounts),
+        ] {
+            match users {
+                Ok(users) => users.into_iter().for_each(|user| registry.add(user)),
+                Err(err) => eprintln!(
+                    "Warning: could not get the users from {}, matching users without them: {:#}",
+                    service, err
+                ),
+            }
+        }
+
+        *INSTANCE
+            .write()
+            .expect("User registry lock has been poisoned.") = Some(Arc::new(registry));
+    }
+
+    /// Finds the full identity of a user, falling back to the given one if it is unknown.
+    pub fn resolve(&self, user: UserIdentity) -> UserIdentity {
+        match self.users.iter().find(|known| known.is_same_user(&user)) {
+            Some(known) => {
+                let mut known = known.clone();
+                known.absorb(user);
+                known
+            }
+            None => user,
+        }
+    }
+
+    fn add(&mut self, user: UserIdentity) {
+        match self
+            .users
+            .iter_mut()
+            .find(|known| known.is_same_user(&user))
+        {
+            Some(known) => known.absorb(user),
+            None => self.users.push(user),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{successful_stdout, Mocks, TestEnv};
+use serde_json::Value;
+
+#[test]
+fn shows_help_without_a_config() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    std::fs::remove_file(env.dir.join("config.yaml")).unwrap();
+
+    let help = successful_stdout(&env.run(&["--help"]));
+
+    for command in ["clean", "list", "export", "report", "validate-config"] {
+        assert!(help.contains(command), "{} is missing: {}", command, help);
+    }
+    assert!(help.contains("requested-desc"), "{}", help);
+    assert!(successful_stdout(&env.run(&["completions", "bash"])).contains("media-cleaner"));
+    assert!(successful_stdout(&env.run(&["man"])).contains(".TH media-cleaner 1"));
+    assert!(successful_stdout(&env.run(&["man", "--refresh"])).contains(".TH media-cleaner 1"));
+}
+
+#[test]
+fn rejects_unknown_arguments() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    for args in [
+        &["--bogus"][..],
+        &["list", "--sort", "loudness"],
+        &["list", "--size", "--name"],
+        &["list", "--sort", "size", "--size-asc"],
+        &["cleanup"],
+    ] {
+        let output = env.run(args);
+
+        assert_eq!(output.status.code(), Some(2), "{:?}", args);
+    }
+    assert!(mocks.requests().is_empty());
+}
+
+#[test]
+fn lists_in_the_chosen_order() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let largest_first = successful_stdout(&env.run(&["list", "--sort", "size"]));
+    let smallest_first = successful_stdout(&env.run(&["list", "--size-asc"]));
+
+    let titles = |list: &str| -> Vec<String> {
+        list.lines()
+            .map(|line| line.split(" (").next().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(
+        titles(&largest_first),
+        ["The Matrix", "Breaking Bad", "Heat"]
+    );
+    assert_eq!(
+        titles(&smallest_first),
+        ["Heat", "Breaking Bad", "The Matrix"]
+    );
+    assert!(largest_first.contains("Heat (Movie, 12.00GB) - last watched never, requested by bob"));
+}
+
+#[test]
+fn exports_json_and_csv() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let json: Value = serde_json::from_str(&successful_stdout(&env.run(&["export"]))).unwrap();
+    let csv = successful_stdout(&env.run(&["export", "--format", "csv"]));
+
+    assert_eq!(json.as_array().unwrap().len(), 3);
+    assert_eq!(json[1]["title"], "Heat");
+    assert_eq!(json[1]["size"], 12000000000_i64);
+    assert_eq!(json[1]["last_watched"], Value::Null);
+    // Alice watched The Matrix herself, which shows without a requester policy as well.
+    assert_eq!(json[2]["watched_by_requester"], true);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("rating_key,title,media_type,size"));
+    assert_eq!(
+        lines[2],
+        "101,Heat,Movie,12000000000,bob,2024-02-01T10:00:00+00:00,,false"
+    );
+}
+
+#[test]
+fn validates_the_config() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    assert!(successful_stdout(&env.run(&["validate-config"]))
+        .contains("The config at ./config.yaml is valid."));
+    assert!(mocks.requests().is_empty());
+
+    env.configure("radarr", "max_concurrent_requests: 0");
+    let output = env.run(&["validate-config"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("max_concurrent_requests for radarr has to be at least 1."));
+}
+
+#[test]
+fn refreshes_only_the_cached_responses() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    // A cache directory shared with other files, here the config itself.
+    env.edit_config(
+        "cache: {ttl_hours: 0}",
+        &format!("cache: {{dir: '{}'}}", env.dir.display()),
+    );
+    let overseerr_requests = || mocks.requests_to("overseerr", "GET").len();
+
+    successful_stdout(&env.run(&["list"]));
+    let uncached = overseerr_requests();
+    successful_stdout(&env.run(&["list"]));
+    assert_eq!(overseerr_requests(), uncached);
+
+    successful_stdout(&env.run(&["list", "--refresh"]));
+
+    assert_eq!(overseerr_requests(), uncached * 2);
+    assert!(env.dir.join("config.yaml").exists());
+    assert!(env.dir.join("overseerr").exists());
+}
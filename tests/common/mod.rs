@@ -0,0 +1,445 @@
+//! Local stand-ins for the services, serving recorded responses from `tests/fixtures`, and helpers
+//! to run the program against them.
+
+#![allow(dead_code)]
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+pub const SERVICES: [&str; 6] = [
+    "overseerr",
+    "plex",
+    "tautulli",
+    "radarr",
+    "radarr_4k",
+    "sonarr",
+];
+
+/// A response that replaces the fixture for a request, for a number of times or for good.
+#[derive(Debug, Clone)]
+struct Override {
+    status: u16,
+    body: String,
+    times: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    requests: Vec<String>,
+    headers: Vec<(&'static str, HeaderMap)>,
+    overrides: HashMap<(String, String), Override>,
+}
+
+#[derive(Clone)]
+struct ServiceState {
+    service: &'static str,
+    shared: Arc<Mutex<MockState>>,
+}
+
+/// The stand-ins for every service, each listening on its own port.
+pub struct Mocks {
+    addresses: HashMap<&'static str, SocketAddr>,
+    shared: Arc<Mutex<MockState>>,
+}
+
+impl Mocks {
+    pub fn start() -> Self {
+        let shared = Arc::new(Mutex::new(MockState::default()));
+        let mut addresses = HashMap::new();
+        let mut servers = Vec::new();
+
+        for service in SERVICES {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            addresses.insert(service, listener.local_addr().unwrap());
+
+            let router = Router::new().fallback(handle).with_state(ServiceState {
+                service,
+                shared: shared.clone(),
+            });
+            servers.push((listener, router));
+        }
+
+        thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+
+            runtime.block_on(async move {
+                let servers = servers.into_iter().map(|(listener, router)| async move {
+                    axum::Server::from_tcp(listener)
+                        .unwrap()
+                        .serve(router.into_make_service())
+                        .await
+                        .unwrap()
+                });
+                futures::future::join_all(servers).await;
+            });
+        });
+
+        Self { addresses, shared }
+    }
+
+    pub fn url(&self, service: &str) -> String {
+        format!("http://{}", self.addresses[service])
+    }
+
+    /// Answers requests to the path with the status and body instead of the fixture.
+    pub fn respond(&self, service: &str, path: &str, status: u16, body: &str) {
+        self.add_override(service, path, status, body, None);
+    }
+
+    /// Answers the first `times` requests to the path with the status, then the fixture again.
+    pub fn fail_times(&self, service: &str, path: &str, status: u16, times: usize) {
+        self.add_override(service, path, status, "", Some(times));
+    }
+
+    fn add_override(
+        &self,
+        service: &str,
+        path: &str,
+        status: u16,
+        body: &str,
+        times: Option<usize>,
+    ) {
+        self.shared.lock().unwrap().overrides.insert(
+            (service.to_string(), path.to_string()),
+            Override {
+                status,
+                body: body.to_string(),
+                times,
+            },
+        );
+    }
+
+    /// Every request made so far, as `service METHOD /path?query`.
+    pub fn requests(&self) -> Vec<String> {
+        self.shared.lock().unwrap().requests.clone()
+    }
+
+    /// The value of the header in every request made to the service, in order.
+    pub fn headers_to(&self, service: &str, header: &str) -> Vec<Option<String>> {
+        self.shared
+            .lock()
+            .unwrap()
+            .headers
+            .iter()
+            .filter(|(to, _)| *to == service)
+            .map(|(_, headers)| {
+                headers
+                    .get(header)
+                    .map(|value| value.to_str().unwrap().to_string())
+            })
+            .collect()
+    }
+
+    /// The requests made to the service with the method, as `/path?query`.
+    pub fn requests_to(&self, service: &str, method: &str) -> Vec<String> {
+        let prefix = format!("{} {} ", service, method);
+        self.requests()
+            .into_iter()
+            .filter_map(|request| request.strip_prefix(&prefix).map(str::to_string))
+            .collect()
+    }
+}
+
+async fn handle(
+    State(state): State<ServiceState>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri.path().to_string();
+    let query: HashMap<String, String> = uri
+        .query()
+        .map(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let override_response = {
+        let mut shared = state.shared.lock().unwrap();
+        let mut line = format!("{} {} {}", state.service, method, uri);
+        if !body.is_empty() {
+            line.push(' ');
+            line.push_str(&String::from_utf8_lossy(&body));
+        }
+        shared.requests.push(line);
+        shared.headers.push((state.service, headers));
+
+        let key = (state.service.to_string(), path.clone());
+        let found = shared.overrides.get_mut(&key).map(|found| {
+            if let Some(ref mut times) = found.times {
+                *times -= 1;
+            }
+            found.clone()
+        });
+        if found.as_ref().is_some_and(|found| found.times == Some(0)) {
+            shared.overrides.remove(&key);
+        }
+        found
+    };
+
+    if let Some(found) = override_response {
+        return (StatusCode::from_u16(found.status).unwrap(), found.body).into_response();
+    }
+
+    match method {
+        Method::GET => match fixture(state.service, &path, &query) {
+            Some((content_type, body)) => ([(CONTENT_TYPE, content_type)], body).into_response(),
+            None => StatusCode::NOT_FOUND.into_response(),
+        },
+        Method::POST => (StatusCode::CREATED, "{}").into_response(),
+        Method::DELETE => StatusCode::OK.into_response(),
+        _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+    }
+}
+
+/// The recorded response for a GET request, with its content type.
+fn fixture(
+    service: &str,
+    path: &str,
+    query: &HashMap<String, String>,
+) -> Option<(&'static str, String)> {
+    let number = |key: &str| -> Option<usize> { query.get(key)?.parse().ok() };
+
+    let file = match (service, path) {
+        ("overseerr", "/api/v1/user") => "user.json".to_string(),
+        // Every user has the same notification settings.
+        ("overseerr", path) if path.ends_with("/settings/notifications") => {
+            "user_notifications.json".to_string()
+        }
+        ("overseerr", path) if path.starts_with("/api/v1/user/") => {
+            let id: u64 = path.strip_prefix("/api/v1/user/")?.parse().ok()?;
+            return Some(("application/json", user(id)?));
+        }
+        ("overseerr", "/api/v1/media") => "media.json".to_string(),
+        ("overseerr", "/api/v1/status") => "status.json".to_string(),
+        ("overseerr", path) if path.starts_with("/api/v1/settings/") => {
+            format!("settings_{}.json", path.strip_prefix("/api/v1/settings/")?)
+        }
+        // Overseerr pages with `take` and `skip`, the recorded pages hold two requests each.
+        ("overseerr", "/api/v1/request") => {
+            let page = number("skip").unwrap_or(0) / 2 + 1;
+            format!("request_page_{}.json", page)
+        }
+        ("plex", "/library/sections") => "sections.xml".to_string(),
+        ("plex", "/accounts") => "accounts.xml".to_string(),
+        ("plex", "/identity") => "identity.xml".to_string(),
+        ("plex", path) => {
+            let section = path
+                .strip_prefix("/library/sections/")?
+                .strip_suffix("/all")?;
+            format!("section_{}.xml", section)
+        }
+        ("tautulli", "/api/v2") => match query.get("cmd")?.as_str() {
+            "get_history" => return Some(("application/json", history_page(query))),
+            cmd => format!("{}.json", cmd),
+        },
+        ("radarr" | "radarr_4k", "/api/v3/movie") => "movie.json".to_string(),
+        ("sonarr", "/api/v3/series") => "series.json".to_string(),
+        ("sonarr", "/api/v3/episode") => format!("episodes_{}.json", number("seriesId")?),
+        (_, "/api/v3/system/status") => "system_status.json".to_string(),
+        _ => return None,
+    };
+
+    let body = fs::read_to_string(fixtures_dir().join(service).join(&file)).ok()?;
+    let content_type = match file.ends_with(".xml") {
+        true => "application/xml",
+        false => "application/json",
+    };
+
+    Some((content_type, body))
+}
+
+/// A single Overseerr user, from the list of users.
+fn user(id: u64) -> Option<String> {
+    let users: Value = serde_json::from_str(
+        &fs::read_to_string(fixtures_dir().join("overseerr/user.json")).unwrap(),
+    )
+    .unwrap();
+
+    users["results"]
+        .as_array()?
+        .iter()
+        .find(|user| user["id"] == id)
+        .map(|user| user.to_string())
+}
+
+/// A page of the Tautulli history. The recorded history is put after a full page of watches of
+/// other items, so the client has to fetch a second page to find it.
+fn history_page(query: &HashMap<String, String>) -> String {
+    let recorded: Value = serde_json::from_str(
+        &fs::read_to_string(fixtures_dir().join("tautulli/get_history.json")).unwrap(),
+    )
+    .unwrap();
+    let length: usize = query["length"].parse().unwrap();
+    let start: usize = query["start"].parse().unwrap();
+
+    let filler = (0..length).map(|i| {
+        let mut row = recorded["response"]["data"]["data"][0].clone();
+        row["rating_key"] = json!(90000 + i);
+        row
+    });
+    let rows: Vec<Value> = filler
+        .chain(
+            recorded["response"]["data"]["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .cloned(),
+        )
+        .collect();
+    let page: Vec<Value> = rows.iter().skip(start).take(length).cloned().collect();
+
+    let mut response = recorded;
+    response["response"]["data"]["recordsTotal"] = json!(rows.len());
+    response["response"]["data"]["recordsFiltered"] = json!(rows.len());
+    response["response"]["data"]["data"] = json!(page);
+    response.to_string()
+}
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// A directory with a config pointing at the mocks, to run the program in.
+pub struct TestEnv {
+    pub dir: PathBuf,
+}
+
+impl TestEnv {
+    /// A config for every service, followed by any extra lines.
+    pub fn new(mocks: &Mocks, extra: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "media-cleaner-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = format!(
+            "plex: {{url: {plex}, token: plex-token}}
+overseerr: {{url: {overseerr}, api_key: overseerr-key}}
+tautulli: {{url: {tautulli}, api_key: tautulli-key}}
+radarr: {{url: {radarr}, api_key: radarr-key}}
+radarr_4k: {{url: {radarr_4k}, api_key: radarr-4k-key}}
+sonarr: {{url: {sonarr}, api_key: sonarr-key}}
+cache: {{ttl_hours: 0}}
+data_dir: {data}
+{extra}
+",
+            plex = mocks.url("plex"),
+            overseerr = mocks.url("overseerr"),
+            tautulli = mocks.url("tautulli"),
+            radarr = mocks.url("radarr"),
+            radarr_4k = mocks.url("radarr_4k"),
+            sonarr = mocks.url("sonarr"),
+            data = dir.join("data").display(),
+            extra = extra,
+        );
+        fs::write(dir.join("config.yaml"), config).unwrap();
+
+        Self { dir }
+    }
+
+    /// Adds settings to the config section of the service, e.g. `max_retries: 0`.
+    pub fn configure(&self, service: &str, settings: &str) {
+        self.edit_config(
+            &format!("\n{}: {{", service),
+            &format!("\n{}: {{{}, ", service, settings),
+        );
+    }
+
+    /// Points the service at a different URL.
+    pub fn set_url(&self, service: &str, mocks: &Mocks, url: &str) {
+        self.edit_config(
+            &format!("\n{}: {{url: {}", service, mocks.url(service)),
+            &format!("\n{}: {{url: {}", service, url),
+        );
+    }
+
+    /// Replaces the first occurrence of the text in the config.
+    pub fn edit_config(&self, from: &str, to: &str) {
+        let path = self.dir.join("config.yaml");
+        let config = format!("\n{}", fs::read_to_string(&path).unwrap());
+        assert!(config.contains(from), "The config has no {}", from);
+        fs::write(path, config.replacen(from, to, 1).trim_start()).unwrap();
+    }
+
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_media-cleaner"));
+        command.args(args).current_dir(&self.dir);
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+
+    pub fn data_file(&self, name: &str) -> Option<Value> {
+        let file = fs::read_to_string(self.dir.join("data").join(name)).ok()?;
+        Some(serde_json::from_str(&file).unwrap())
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// The output of a command that has to succeed.
+pub fn successful_stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "The command failed: {}",
+        stderr(output)
+    );
+    stdout(output)
+}
+
+/// The text of a section in the markdown report, up to the next heading.
+pub fn report_section<'a>(report: &'a str, heading: &str) -> &'a str {
+    let start = report
+        .find(&format!("## {}", heading))
+        .unwrap_or_else(|| panic!("The report has no {} section.", heading));
+    let rest = &report[start + heading.len() + 3..];
+    match rest.find("\n## ") {
+        Some(end) => &rest[..end],
+        None => rest,
+    }
+}
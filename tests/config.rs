@@ -0,0 +1,246 @@
+mod common;
+
+use common::{stderr, Mocks, TestEnv};
+use serde_json::{json, Value};
+use std::fs;
+
+/// Moves the config out of the working directory, to the path relative to it.
+fn move_config(env: &TestEnv, to: &str) {
+    let path = env.dir.join(to);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::rename(env.dir.join("config.yaml"), path).unwrap();
+}
+
+#[test]
+fn reads_the_config_from_a_path() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    move_config(&env, "elsewhere/cleaner.yaml");
+    let path = env.dir.join("elsewhere/cleaner.yaml");
+
+    let from_flag = env
+        .command(&["validate-config", "--config", path.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let from_env = env
+        .command(&["validate-config"])
+        .env("MEDIA_CLEANER_CONFIG", &path)
+        .output()
+        .unwrap();
+
+    for output in [from_flag, from_env] {
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert!(String::from_utf8_lossy(&output.stdout).contains("cleaner.yaml is valid."));
+    }
+}
+
+#[test]
+fn finds_the_config_in_the_xdg_config_dir() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    move_config(&env, "xdg/media-cleaner/config.yaml");
+
+    let output = env
+        .command(&["report"])
+        .env("XDG_CONFIG_HOME", env.dir.join("xdg"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("covering 3 items"));
+}
+
+#[test]
+fn lists_the_locations_without_a_config() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    fs::remove_file(env.dir.join("config.yaml")).unwrap();
+
+    let output = env
+        .command(&["report"])
+        .env("XDG_CONFIG_HOME", env.dir.join("xdg"))
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("No config file found, looked for ./config.yaml"));
+    assert!(stderr(&output).contains("/etc/media-cleaner/config.yaml"));
+    assert!(mocks.requests().is_empty());
+}
+
+#[test]
+fn overrides_fields_from_the_environment() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env
+        .command(&["report"])
+        .env("MEDIA_CLEANER_TAUTULLI__API_KEY", "12345")
+        .env("MEDIA_CLEANER_OVERSEERR__MAX_RETRIES", "0")
+        .env("MEDIA_CLEANER_ITEMS_SHOWN", "3")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(mocks
+        .requests_to("tautulli", "GET")
+        .iter()
+        .all(|request| request.contains("apikey=12345")));
+}
+
+#[test]
+fn reads_the_whole_config_from_the_environment() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    fs::remove_file(env.dir.join("config.yaml")).unwrap();
+
+    let mut command = env.command(&["report"]);
+    command
+        .env("XDG_CONFIG_HOME", env.dir.join("xdg"))
+        .env("MEDIA_CLEANER_DATA_DIR", env.dir.join("data"))
+        .env("MEDIA_CLEANER_CACHE__TTL_HOURS", "0");
+    for (service, key) in [
+        ("plex", "token"),
+        ("overseerr", "api_key"),
+        ("tautulli", "api_key"),
+        ("radarr", "api_key"),
+        ("radarr_4k", "api_key"),
+        ("sonarr", "api_key"),
+    ] {
+        let prefix = format!("MEDIA_CLEANER_{}__", service.to_uppercase());
+        command
+            .env(format!("{}URL", prefix), mocks.url(service))
+            // Keys of only digits stay strings, even without a field in a file to replace.
+            .env(format!("{}{}", prefix, key.to_uppercase()), "12345");
+    }
+    let output = command.output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("covering 3 items"));
+}
+
+#[test]
+fn reads_secrets_from_files() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    fs::write(env.dir.join("plex_token"), "file-token\n").unwrap();
+    env.edit_config(
+        "token: plex-token",
+        &format!("token_file: {}", env.dir.join("plex_token").display()),
+    );
+
+    let output = env.run(&["report"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(mocks
+        .headers_to("plex", "x-plex-token")
+        .iter()
+        .all(|token| token.as_deref() == Some("file-token")));
+}
+
+#[test]
+fn needs_every_secret() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    env.edit_config(", api_key: sonarr-key", "");
+
+    let output = env.run(&["validate-config"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("sonarr needs either api_key or api_key_file to be set."));
+}
+
+/// Renames the Radarr and Sonarr sections so they are ignored, and has them looked up in Overseerr
+/// instead, where they point at the mocks. With `extra_server`, Overseerr has another standard
+/// server of each kind first.
+fn discover_servers(mocks: &Mocks, env: &TestEnv, extra_server: bool) {
+    for service in ["radarr", "radarr_4k", "sonarr"] {
+        let line = format!("\n{}: {{url: {}", service, mocks.url(service));
+        env.edit_config(&line, &format!("\nunused_{}: {{url: ", service));
+    }
+    env.configure("overseerr", "discover_servers: true");
+
+    for (media, services) in [
+        ("radarr", vec!["radarr", "radarr_4k"]),
+        ("sonarr", vec!["sonarr"]),
+    ] {
+        let path = format!("tests/fixtures/overseerr/settings_{}.json", media);
+        let mut servers: Vec<Value> =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        for (server, service) in servers.iter_mut().zip(services) {
+            let address = mocks.url(service);
+            let (host, port) = address
+                .strip_prefix("http://")
+                .unwrap()
+                .split_once(':')
+                .unwrap();
+            server["hostname"] = json!(host);
+            server["port"] = json!(port.parse::<u16>().unwrap());
+        }
+        if extra_server {
+            let mut other = servers[0].clone();
+            other["name"] = json!("Old server");
+            other["port"] = json!(1);
+            other["isDefault"] = json!(false);
+            servers.insert(0, other);
+        }
+
+        mocks.respond(
+            "overseerr",
+            &format!("/api/v1/settings/{}", media),
+            200,
+            &json!(servers).to_string(),
+        );
+    }
+}
+
+#[test]
+fn discovers_servers_from_overseerr() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    discover_servers(&mocks, &env, false);
+
+    let output = env.run(&["report"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("covering 3 items"));
+    for (service, key) in [
+        ("radarr", "radarr-key"),
+        ("radarr_4k", "radarr-4k-key"),
+        ("sonarr", "sonarr-key"),
+    ] {
+        let keys = mocks.headers_to(service, "x-api-key");
+        assert!(!keys.is_empty(), "{} was never contacted", service);
+        assert!(keys.iter().all(|found| found.as_deref() == Some(key)));
+    }
+}
+
+#[test]
+fn refuses_to_choose_between_discovered_servers() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    discover_servers(&mocks, &env, true);
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(
+        stderr(&output)
+            .contains("Overseerr has 2 servers that could be radarr (\"Old server\", \"Radarr\")"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(mocks.requests_to("radarr", "GET").is_empty());
+}
+
+#[test]
+fn validates_a_discovering_config_offline() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    discover_servers(&mocks, &env, false);
+
+    let output = env.run(&["validate-config"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(mocks.requests().is_empty());
+}
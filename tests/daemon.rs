@@ -0,0 +1,170 @@
+mod common;
+
+use chrono::DateTime;
+use common::{Mocks, TestEnv};
+use serde_json::json;
+use std::{
+    fs,
+    process::{Child, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// A config with the settings in the daemon's section, a schedule that runs every second, and
+/// requester notifications going to a webhook on the Overseerr mock.
+fn daemon_env(mocks: &Mocks, settings: &str) -> TestEnv {
+    TestEnv::new(
+        mocks,
+        &format!(
+            "daemon: {{schedule: \"* * * * * *\", {}}}\nrequester_notifications: {{webhook_url: \"{}/hook\"}}",
+            settings,
+            mocks.url("overseerr")
+        ),
+    )
+}
+
+/// The daemon running against the mocks, stopped when dropped.
+struct Daemon(Child);
+
+impl Daemon {
+    fn start(env: &TestEnv) -> Self {
+        let child = env
+            .command(&["daemon"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        Self(child)
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Polls the mocks until the check passes, for up to 20 seconds.
+fn wait_for(mocks: &Mocks, done: impl Fn(&Mocks) -> bool) {
+    for _ in 0..200 {
+        if done(mocks) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    panic!(
+        "Gave up waiting, after these requests: {:#?}",
+        mocks.requests()
+    );
+}
+
+/// The webhook notifications sent to requesters.
+fn notifications(mocks: &Mocks) -> Vec<String> {
+    mocks
+        .requests_to("overseerr", "POST")
+        .into_iter()
+        .filter(|request| request.starts_with("/hook"))
+        .collect()
+}
+
+#[test]
+fn removes_right_away_without_a_grace_period() {
+    let mocks = Mocks::start();
+    let env = daemon_env(
+        &mocks,
+        "grace_days: 0, rules: {media_type: movie, min_size_gb: 50}",
+    );
+    let daemon = Daemon::start(&env);
+
+    wait_for(&mocks, |mocks| {
+        notifications(mocks)
+            .iter()
+            .any(|notification| notification.contains("\"removed\""))
+    });
+    drop(daemon);
+
+    let deleted = mocks.requests_to("radarr", "DELETE");
+    assert!(!deleted.is_empty(), "{:#?}", mocks.requests());
+    let notifications = notifications(&mocks);
+    assert!(
+        notifications
+            .iter()
+            .all(|notification| !notification.contains("\"upcoming\"")),
+        "{:#?}",
+        notifications
+    );
+    assert!(
+        notifications[0].contains("The Matrix") && notifications[0].contains("123456789012345678"),
+        "{:#?}",
+        notifications
+    );
+}
+
+#[test]
+fn queues_items_for_the_grace_period() {
+    let mocks = Mocks::start();
+    let env = daemon_env(
+        &mocks,
+        "grace_days: 7, rules: {media_type: movie, min_size_gb: 50}",
+    );
+    let daemon = Daemon::start(&env);
+
+    wait_for(&mocks, |mocks| !notifications(mocks).is_empty());
+    // Give it a few more runs, which should neither remove it nor tell the requester again.
+    thread::sleep(Duration::from_secs(3));
+    drop(daemon);
+
+    assert!(mocks.requests_to("radarr", "DELETE").is_empty());
+    let notifications = notifications(&mocks);
+    assert_eq!(notifications.len(), 1, "{:#?}", notifications);
+    assert!(
+        notifications[0].contains("\"upcoming\"") && notifications[0].contains("The Matrix"),
+        "{:#?}",
+        notifications
+    );
+
+    let queue = env.data_file("queue.json").unwrap();
+    assert_eq!(queue.as_array().unwrap().len(), 1, "{}", queue);
+    assert_eq!(queue[0]["title"], "The Matrix");
+    let queued_at = DateTime::parse_from_rfc3339(queue[0]["queued_at"].as_str().unwrap()).unwrap();
+    let removal_date =
+        DateTime::parse_from_rfc3339(queue[0]["removal_date"].as_str().unwrap()).unwrap();
+    assert_eq!(removal_date - queued_at, chrono::Duration::days(7));
+}
+
+#[test]
+fn removes_queued_items_once_their_grace_period_is_over() {
+    let mocks = Mocks::start();
+    let env = daemon_env(
+        &mocks,
+        "grace_days: 7, rules: {media_type: movie, min_size_gb: 50}",
+    );
+    fs::create_dir_all(env.dir.join("data")).unwrap();
+    fs::write(
+        env.dir.join("data/queue.json"),
+        json!([{
+            "rating_key": "100",
+            "title": "The Matrix",
+            "queued_at": "2024-06-01T12:00:00Z",
+            "removal_date": "2024-06-08T12:00:00Z",
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    let daemon = Daemon::start(&env);
+
+    wait_for(&mocks, |mocks| {
+        notifications(mocks)
+            .iter()
+            .any(|notification| notification.contains("\"removed\""))
+    });
+    drop(daemon);
+
+    let deleted = mocks.requests_to("radarr", "DELETE");
+    assert!(!deleted.is_empty(), "{:#?}", mocks.requests());
+    assert!(notifications(&mocks)
+        .iter()
+        .all(|notification| !notification.contains("\"upcoming\"")));
+}
@@ -0,0 +1,411 @@
+mod common;
+
+use common::{Mocks, TestEnv};
+use serde_json::{json, Value};
+use std::{
+    net::TcpListener,
+    process::{Child, Stdio},
+    time::Duration,
+};
+
+/// The web server running against the mocks, stopped when dropped.
+struct Server {
+    child: Child,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl Server {
+    /// Starts the server and waits until it has gathered the items.
+    async fn start(env: &TestEnv) -> Self {
+        Self::start_with(env, "").await
+    }
+
+    /// Starts the server with more settings in its section of the config, e.g. `token: abc`.
+    async fn start_with(env: &TestEnv, settings: &str) -> Self {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config = std::fs::read_to_string(env.dir.join("config.yaml")).unwrap();
+        std::fs::write(
+            env.dir.join("config.yaml"),
+            format!(
+                "{}server: {{address: \"{}\", {}}}\n",
+                config, address, settings
+            ),
+        )
+        .unwrap();
+
+        let child = env
+            .command(&["serve"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Self {
+            child,
+            url: format!("http://{}", address),
+            client: reqwest::Client::new(),
+        };
+
+        server
+            .wait_for("/health", |health| {
+                health["gathered_at"].is_string() && !health["gathering"].as_bool().unwrap()
+            })
+            .await;
+        server
+    }
+
+    async fn get(&self, path: &str) -> Option<Value> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .ok()?;
+        response.json().await.ok()
+    }
+
+    /// Polls the path until the response passes the check, for up to 20 seconds.
+    async fn wait_for(&self, path: &str, done: impl Fn(&Value) -> bool) -> Value {
+        for _ in 0..200 {
+            if let Some(body) = self.get(path).await {
+                if done(&body) {
+                    return body;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        panic!("{} never got to the expected state.", path);
+    }
+
+    /// Deletes the items with the rating keys and waits for the run to finish.
+    async fn delete(&self, ids: &[&str]) -> Value {
+        let started: Value = self
+            .client
+            .post(format!("{}/delete", self.url))
+            .json(&json!({ "ids": ids }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        self.wait_for(&format!("/runs/{}", started["id"]), |run| {
+            run["finished_at"].is_string()
+        })
+        .await
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn titles(items: &Value) -> Vec<&str> {
+    items
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["title"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn deletes_chosen_items_everywhere() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let server = Server::start(&env).await;
+
+    let candidates = server.get("/candidates").await.unwrap();
+    let mut candidate_titles = titles(&candidates["items"]);
+    candidate_titles.sort();
+    assert_eq!(candidate_titles, ["Breaking Bad", "Heat", "The Matrix"]);
+
+    let run = server.delete(&["100", "200"]).await;
+
+    let mut deleted_titles = titles(&run["deleted"]);
+    deleted_titles.sort();
+    assert_eq!(deleted_titles, ["Breaking Bad", "The Matrix"]);
+    assert_eq!(run["errors"], json!([]));
+    assert_eq!(
+        mocks.requests_to("radarr", "DELETE"),
+        ["/api/v3/movie/1?deleteFiles=true&addImportExclusion=false"]
+    );
+    assert_eq!(
+        mocks.requests_to("radarr_4k", "DELETE"),
+        ["/api/v3/movie/11?deleteFiles=true&addImportExclusion=false"]
+    );
+    assert_eq!(
+        mocks.requests_to("sonarr", "DELETE"),
+        ["/api/v3/series/1?deleteFiles=true&addImportListExclusion=false"]
+    );
+    let mut overseerr = mocks.requests_to("overseerr", "DELETE");
+    overseerr.sort();
+    assert_eq!(overseerr, ["/api/v1/media/10", "/api/v1/media/12"]);
+
+    // The files are removed before the request.
+    let requests = mocks.requests();
+    let position = |request: &str| requests.iter().position(|r| r == request).unwrap();
+    assert!(
+        position("radarr_4k DELETE /api/v3/movie/11?deleteFiles=true&addImportExclusion=false")
+            < position("overseerr DELETE /api/v1/media/10")
+    );
+
+    let deleted = env.data_file("deleted_items.json").unwrap();
+    let deleted_item = |title: &str| {
+        deleted
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|item| item["item"]["title"] == title)
+            .unwrap()
+            .clone()
+    };
+    let matrix = deleted_item("The Matrix");
+    assert_eq!(matrix["instances"][0]["tmdb_id"], 603);
+    assert_eq!(matrix["instances"][0]["quality_profile_id"], 4);
+    assert_eq!(matrix["instances"][1]["is_4k"], true);
+    assert_eq!(
+        matrix["instances"][1]["root_folder_path"],
+        "/data/movies-4k"
+    );
+    let breaking_bad = deleted_item("Breaking Bad");
+    assert_eq!(breaking_bad["instances"][0]["tvdb_id"], 81189);
+    assert_eq!(breaking_bad["request"]["tmdb_id"], 1396);
+    assert_eq!(breaking_bad["request"]["requested_by"], "bob");
+    assert!(env.data_file("deletion_plan.json").is_none());
+
+    let candidates = server.get("/candidates").await.unwrap();
+    assert_eq!(titles(&candidates["items"]), ["Heat"]);
+}
+
+#[tokio::test]
+async fn keeps_the_request_when_files_remain() {
+    let mocks = Mocks::start();
+    mocks.respond("radarr_4k", "/api/v3/movie/11", 500, "Database is locked");
+    let env = TestEnv::new(&mocks, "");
+    env.configure("radarr_4k", "max_retries: 0");
+    let server = Server::start(&env).await;
+
+    let run = server.delete(&["100"]).await;
+
+    assert_eq!(run["deleted"], json!([]));
+    let services = &run["outcomes"][0]["services"];
+    assert_eq!(
+        services[0],
+        json!({"service": "radarr", "status": "removed"})
+    );
+    assert_eq!(services[1]["service"], "radarr_4k");
+    assert_eq!(services[1]["status"], "failed");
+    assert!(services[1]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("Got 500 Internal Server Error from Radarr"));
+    assert_eq!(services[2]["service"], "overseerr");
+    assert_eq!(services[2]["status"], "skipped");
+    assert!(mocks.requests_to("overseerr", "DELETE").is_empty());
+
+    // What was removed can still be restored.
+    let deleted = env.data_file("deleted_items.json").unwrap();
+    assert_eq!(deleted[0]["instances"].as_array().unwrap().len(), 1);
+    assert_eq!(deleted[0]["request"], Value::Null);
+}
+
+#[tokio::test]
+async fn keeps_webhook_tokens_out_of_errors() {
+    let mocks = Mocks::start();
+    mocks.respond(
+        "overseerr",
+        "/api/webhooks/1/secret-token",
+        404,
+        r#"{"message": "Unknown Webhook"}"#,
+    );
+    let env = TestEnv::new(
+        &mocks,
+        &format!(
+            "webhooks: [{{url: '{}/api/webhooks/1/secret-token'}}, {{url: 'http://127.0.0.1:1/api/webhooks/2/other-token'}}]",
+            mocks.url("overseerr")
+        ),
+    );
+    let server = Server::start(&env).await;
+
+    let run = server.delete(&["101"]).await;
+
+    let errors = run["notification_errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 2, "{:?}", errors);
+    for error in errors {
+        assert_eq!(error["title"], "127.0.0.1");
+        assert!(
+            error["error"].as_str().unwrap().contains("127.0.0.1"),
+            "{}",
+            error
+        );
+        assert!(!error.to_string().contains("token"), "{}", error);
+    }
+}
+
+#[tokio::test]
+async fn gives_up_on_slow_webhooks() {
+    let mocks = Mocks::start();
+    // Accepts the connection, but never answers.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let env = TestEnv::new(
+        &mocks,
+        &format!(
+            "webhooks: [{{url: 'http://{}/hook', timeout_seconds: 1}}]",
+            listener.local_addr().unwrap()
+        ),
+    );
+    let server = Server::start(&env).await;
+
+    let run = server.delete(&["101"]).await;
+
+    assert_eq!(run["deleted"].as_array().unwrap().len(), 1);
+    let error = run["notification_errors"][0]["error"].as_str().unwrap();
+    assert!(
+        error.contains("Webhook did not respond at 127.0.0.1 within 1 seconds"),
+        "{}",
+        error
+    );
+}
+
+#[tokio::test]
+async fn needs_the_token_to_delete() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let server = Server::start_with(&env, "token: secret-token").await;
+    let delete = |token: Option<&str>| {
+        let request = server
+            .client
+            .post(format!("{}/delete", server.url))
+            .json(&json!({ "ids": ["101"] }));
+        match token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+        .send()
+    };
+
+    assert_eq!(delete(None).await.unwrap().status(), 401);
+    assert_eq!(delete(Some("wrong")).await.unwrap().status(), 401);
+    assert!(mocks.requests_to("radarr", "DELETE").is_empty());
+
+    assert_eq!(delete(Some("secret-token")).await.unwrap().status(), 202);
+    // Reading needs no token.
+    assert!(server.get("/candidates").await.is_some());
+}
+
+#[tokio::test]
+async fn sorts_candidates_like_the_command_line() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let server = Server::start(&env).await;
+
+    let candidates = server.get("/candidates?sort=size-asc").await.unwrap();
+    let unknown = server.get("/candidates?sort=sa").await.unwrap();
+
+    assert_eq!(
+        titles(&candidates["items"]),
+        ["Heat", "Breaking Bad", "The Matrix"]
+    );
+    assert_eq!(unknown["error"], "Not a valid sorting option.");
+}
+
+#[tokio::test]
+async fn rejects_unknown_items() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let server = Server::start(&env).await;
+
+    let response = server
+        .client
+        .post(format!("{}/delete", server.url))
+        .json(&json!({ "ids": ["999"] }))
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_client_error());
+    assert!(mocks
+        .requests()
+        .iter()
+        .all(|request| !request.contains(" DELETE ")));
+}
+
+#[tokio::test]
+async fn fits_long_summaries_in_a_discord_embed() {
+    let mocks = Mocks::start();
+    let long_title = "And a Very Long Subtitle".repeat(100);
+    let section = std::fs::read_to_string("tests/fixtures/plex/section_1.xml")
+        .unwrap()
+        .replace("title=\"Heat\"", &format!("title=\"Heat{}\"", long_title))
+        .replace(
+            "title=\"The Matrix\"",
+            &format!("title=\"The Matrix{}\"", long_title),
+        );
+    mocks.respond("plex", "/library/sections/1/all", 200, &section);
+    let env = TestEnv::new(
+        &mocks,
+        &format!(
+            "webhooks: [{{url: '{}/discord', format: discord}}]",
+            mocks.url("overseerr")
+        ),
+    );
+    let server = Server::start(&env).await;
+
+    let run = server.delete(&["100", "101", "200"]).await;
+
+    assert_eq!(run["notification_errors"], json!([]), "{}", run);
+    let posted = mocks.requests_to("overseerr", "POST");
+    let body = posted[0].strip_prefix("/discord ").unwrap();
+    let payload: Value = serde_json::from_str(body).unwrap();
+    let description = payload["embeds"][0]["description"].as_str().unwrap();
+    assert!(description.chars().count() <= 4096, "{}", description);
+    assert!(
+        description.starts_with("Deleted 3 items."),
+        "{}",
+        description
+    );
+    assert!(description.ends_with("...and 1 more."), "{}", description);
+}
+
+#[tokio::test]
+async fn counts_candidates_per_overseerr_user() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let server = Server::start(&env).await;
+
+    let metrics = server
+        .client
+        .get(format!("{}/metrics", server.url))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    let requesters: Vec<&str> = metrics
+        .lines()
+        .filter(|line| line.starts_with("media_cleaner_requester_items{"))
+        .collect();
+    assert_eq!(requesters.len(), 2, "{:?}", requesters);
+    for expected in [
+        r#"{requester="alice",requester_id="1"} 1"#,
+        r#"{requester="bob",requester_id="2"} 2"#,
+    ] {
+        assert!(
+            requesters.iter().any(|line| line.ends_with(expected)),
+            "{} is missing: {:?}",
+            expected,
+            requesters
+        );
+    }
+}
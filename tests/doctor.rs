@@ -0,0 +1,122 @@
+mod common;
+
+use common::{stderr, stdout, Mocks, TestEnv};
+use std::process::Output;
+
+/// The line for the service that mentions the text, without the colours.
+fn line_for(output: &Output, service: &str, text: &str) -> String {
+    let plain = String::from_utf8(strip_ansi(&output.stdout)).unwrap();
+    plain
+        .lines()
+        .find(|line| line.starts_with(service) && line.contains(text))
+        .unwrap_or_else(|| panic!("No {} line with {} in:\n{}", service, text, plain))
+        .to_string()
+}
+
+fn strip_ansi(bytes: &[u8]) -> Vec<u8> {
+    let mut plain = Vec::new();
+    let mut in_escape = false;
+    for &byte in bytes {
+        match (in_escape, byte) {
+            (false, 0x1b) => in_escape = true,
+            (false, _) => plain.push(byte),
+            (true, b'm') => in_escape = false,
+            (true, _) => (),
+        }
+    }
+    plain
+}
+
+#[test]
+fn checks_every_service() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["doctor"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(line_for(&output, "Overseerr", "Version 1.33.2").contains("ok"));
+    assert!(line_for(&output, "Overseerr", "CSRF protection is off").contains("ok"));
+    assert!(line_for(&output, "Plex", "1.40.4.8679").contains("2 movie and show libraries"));
+    assert!(line_for(&output, "Tautulli", "Living Room").contains("ok"));
+    line_for(&output, "Radarr 4K", "Matches \"Radarr 4K\" in Overseerr");
+    line_for(&output, "Radarr 4K", "Version 5.8.3.8933");
+    line_for(&output, "Sonarr", "Version 4.0.9.2244");
+    assert!(stdout(&output).contains("Everything looks good."));
+
+    // The status of the services is never read from the cache.
+    assert_eq!(
+        mocks.headers_to("plex", "x-plex-token")[1..],
+        [Some("plex-token".to_string())]
+    );
+}
+
+#[test]
+fn warns_about_csrf() {
+    let mocks = Mocks::start();
+    mocks.respond(
+        "overseerr",
+        "/api/v1/settings/main",
+        200,
+        r#"{"csrfProtection": true}"#,
+    );
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["doctor"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let line = line_for(&output, "Overseerr", "CSRF protection is on");
+    assert!(line.contains("warning"), "{}", line);
+    assert!(stdout(&output).contains("1 checks gave a warning"));
+}
+
+#[test]
+fn finds_mismatched_4k_servers() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    // The 4K Radarr is set up as the standard one and the other way around.
+    env.edit_config("api_key: radarr-key}", "api_key: swapped}");
+    env.edit_config("api_key: radarr-4k-key}", "api_key: radarr-key}");
+    env.edit_config("api_key: swapped}", "api_key: radarr-4k-key}");
+
+    let output = env.run(&["doctor"]);
+
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    let line = line_for(&output, "Radarr 4K", "Overseerr");
+    assert!(line.contains("failed"), "{}", line);
+    assert!(line.contains("Is set up as radarr_4k here, but \"Radarr\" is a standard server"));
+    assert!(stderr(&output).contains("2 of"), "{}", stderr(&output));
+}
+
+#[test]
+fn reports_refused_keys() {
+    let mocks = Mocks::start();
+    mocks.respond("sonarr", "/api/v3/system/status", 401, "");
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["doctor"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    let line = line_for(&output, "Sonarr", "401 Unauthorized");
+    assert!(line.contains("failed"), "{}", line);
+    assert!(stderr(&output).contains("1 of"), "{}", stderr(&output));
+    // The other services are still checked.
+    line_for(&output, "Tautulli", "Living Room");
+}
+
+#[test]
+fn reports_failed_discovery() {
+    let mocks = Mocks::start();
+    mocks.respond("overseerr", "/api/v1/settings/radarr", 404, "");
+    let env = TestEnv::new(&mocks, "");
+    env.configure("overseerr", "discover_servers: true");
+
+    let output = env.run(&["doctor"]);
+
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    let line = line_for(&output, "Overseerr", "Could not get the Radarr servers");
+    assert!(line.contains("failed"), "{}", line);
+    // The servers in the config are still checked.
+    line_for(&output, "Sonarr", "Version 4.0.9.2244");
+    line_for(&output, "Plex", "1.40.4.8679");
+}
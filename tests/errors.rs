@@ -0,0 +1,136 @@
+mod common;
+
+use common::{stderr, Mocks, TestEnv};
+use std::net::TcpListener;
+
+#[test]
+fn refused_api_key() {
+    let mocks = Mocks::start();
+    mocks.respond("radarr", "/api/v3/movie", 401, "");
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(stderr(&output)
+        .contains("Got 401 Unauthorized from Radarr, please check the appropriate API key."));
+}
+
+#[test]
+fn wrong_url() {
+    let mocks = Mocks::start();
+    mocks.respond("sonarr", "/api/v3/series", 404, "Not Found");
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Got 404 Not Found from Sonarr at path /series."));
+}
+
+#[test]
+fn changed_response() {
+    let mocks = Mocks::start();
+    mocks.respond("radarr_4k", "/api/v3/movie", 200, r#"{"records": []}"#);
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(4), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Could not read the response from Radarr at /movie"));
+    assert!(stderr(&output).contains(r#"The response was: {"records": []}"#));
+}
+
+#[test]
+fn unreachable_service() {
+    let mocks = Mocks::start();
+    // A port nothing listens on, once the listener is dropped.
+    let closed = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let env = TestEnv::new(&mocks, "");
+    env.set_url("tautulli", &mocks, &format!("http://{}", closed));
+    env.configure("tautulli", "max_retries: 1");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(5), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Tautulli could not be reached at /api/v2"));
+    assert!(stderr(&output).contains("Gave up after 2 attempts."));
+    assert!(
+        !stderr(&output).contains("tautulli-key"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn retries_unavailable_service() {
+    let mocks = Mocks::start();
+    mocks.fail_times("overseerr", "/api/v1/user", 503, 2);
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let users = mocks
+        .requests_to("overseerr", "GET")
+        .into_iter()
+        .filter(|request| request.starts_with("/api/v1/user"))
+        .count();
+    assert_eq!(users, 3);
+}
+
+#[test]
+fn unavailable_after_retries() {
+    let mocks = Mocks::start();
+    mocks.respond("overseerr", "/api/v1/request", 502, "Bad Gateway");
+    let env = TestEnv::new(&mocks, "");
+    env.configure("overseerr", "max_retries: 0");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(5), "{}", stderr(&output));
+    assert!(stderr(&output)
+        .contains("Got 502 Bad Gateway from Overseerr, it may be down or overloaded."));
+}
+
+#[test]
+fn redacts_secrets() {
+    let mocks = Mocks::start();
+    mocks.respond(
+        "tautulli",
+        "/api/v2",
+        401,
+        r#"{"response": {"result": "error", "message": "Invalid apikey tautulli-key"}}"#,
+    );
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("Invalid apikey [redacted]"),
+        "{}",
+        stderr(&output)
+    );
+    assert!(
+        !stderr(&output).contains("tautulli-key"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn gathers_without_the_plex_accounts() {
+    let mocks = Mocks::start();
+    mocks.respond("plex", "/accounts", 401, "");
+    let env = TestEnv::new(&mocks, "");
+
+    let output = env.run(&["report"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Warning: could not get the users from Plex"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("covering 3 items"));
+}
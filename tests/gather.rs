@@ -0,0 +1,223 @@
+mod common;
+
+use common::{report_section, Mocks, TestEnv};
+
+fn report(env: &TestEnv, args: &[&str]) -> String {
+    let output = env.run(&[&["report"], args].concat());
+    assert!(
+        output.status.success(),
+        "The report failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn gathers_every_request() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let report = report(&env, &[]);
+
+    assert!(report.contains("covering 3 items"), "{}", report);
+    for title in ["The Matrix", "Heat", "Breaking Bad"] {
+        assert!(report.contains(title), "{} is missing: {}", title, report);
+    }
+    assert!(!report.contains("could not be gathered"), "{}", report);
+}
+
+#[test]
+fn follows_overseerr_pages() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let report = report(&env, &[]);
+
+    let requests = mocks.requests_to("overseerr", "GET");
+    assert!(requests.contains(&"/api/v1/request?take=100&".to_string()));
+    assert!(requests.contains(&"/api/v1/request?take=2&skip=2".to_string()));
+    // Breaking Bad is only on the second page.
+    assert!(report.contains("Breaking Bad"), "{}", report);
+}
+
+#[test]
+fn follows_history_pages() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let report = report(&env, &[]);
+
+    let history = mocks
+        .requests_to("tautulli", "GET")
+        .into_iter()
+        .filter(|request| request.contains("cmd=get_history"))
+        .collect::<Vec<_>>();
+    assert_eq!(history.len(), 2, "{:?}", history);
+    assert!(history[1].contains("start=5000"), "{:?}", history);
+
+    // The watches of The Matrix and Breaking Bad are only on the second page.
+    let never_watched = report_section(&report, "Largest never watched items");
+    assert!(never_watched.contains("Heat"), "{}", never_watched);
+    assert!(!never_watched.contains("The Matrix"), "{}", never_watched);
+    assert!(!never_watched.contains("Breaking Bad"), "{}", never_watched);
+}
+
+#[test]
+fn reads_4k_instances() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let report = report(&env, &[]);
+
+    assert_eq!(mocks.requests_to("radarr_4k", "GET"), ["/api/v3/movie?"]);
+    let duplicates = report_section(&report, "4K duplicates");
+    assert!(
+        duplicates.contains("1 items are in both a standard and a 4K instance"),
+        "{}",
+        duplicates
+    );
+    assert!(duplicates.contains("The Matrix"), "{}", duplicates);
+    assert!(duplicates.contains("60.00GB"), "{}", duplicates);
+}
+
+#[test]
+fn includes_media_without_requests() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    let report = report(&env, &["-C"]);
+
+    assert!(report.contains("covering 4 items"), "{}", report);
+    assert!(report.contains("Inception"), "{}", report);
+    assert!(mocks
+        .requests_to("overseerr", "GET")
+        .contains(&"/api/v1/media?take=100&filter=available".to_string()));
+}
+
+#[test]
+fn sends_the_api_keys() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    report(&env, &[]);
+
+    // Tautulli only takes its key as a parameter, the others get theirs in a header.
+    let tautulli = mocks.requests_to("tautulli", "GET");
+    assert!(tautulli
+        .iter()
+        .all(|request| request.contains("apikey=tautulli-key")));
+    let plex = mocks.requests_to("plex", "GET");
+    assert!(plex.iter().all(|request| !request.contains("plex-token")));
+    assert!(mocks
+        .headers_to("plex", "x-plex-token")
+        .iter()
+        .all(|token| token.as_deref() == Some("plex-token")));
+    assert!(mocks
+        .headers_to("radarr", "x-api-key")
+        .iter()
+        .all(|key| key.as_deref() == Some("radarr-key")));
+}
+
+#[test]
+fn checks_every_watch_up_to_the_last_episode_on_disk() {
+    let mocks = Mocks::start();
+    // Only the first episode is on disk, after that there is a gap.
+    let episodes: Vec<serde_json::Value> = serde_json::from_str(
+        &std::fs::read_to_string("tests/fixtures/sonarr/episodes_1.json").unwrap(),
+    )
+    .unwrap();
+    let episodes: Vec<serde_json::Value> = episodes
+        .into_iter()
+        .map(|mut episode| {
+            let first = episode["seasonNumber"] == 1 && episode["episodeNumber"] == 1;
+            episode["hasFile"] = serde_json::json!(first || episode["seasonNumber"] == 0);
+            episode
+        })
+        .collect();
+    mocks.respond(
+        "sonarr",
+        "/api/v3/episode",
+        200,
+        &serde_json::to_string(&episodes).unwrap(),
+    );
+    let env = TestEnv::new(&mocks, "requester_policy: {min_progress: 90}");
+
+    let output = env.run(&["export"]);
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let items: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let breaking_bad = items
+        .iter()
+        .find(|item| item["title"] == "Breaking Bad")
+        .unwrap();
+    // Bob watched the episode fully, then started it again. Specials don't count.
+    assert_eq!(breaking_bad["watched_by_requester"], true);
+    assert!(mocks
+        .requests_to("sonarr", "GET")
+        .contains(&"/api/v3/episode?seriesId=1".to_string()));
+}
+
+#[test]
+fn counts_quotas_per_overseerr_user() {
+    let mocks = Mocks::start();
+    // Bob renamed himself in Overseerr between his requests, he is still the same user.
+    let mut requests: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("tests/fixtures/overseerr/request_page_1.json").unwrap(),
+    )
+    .unwrap();
+    let second_page: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string("tests/fixtures/overseerr/request_page_2.json").unwrap(),
+    )
+    .unwrap();
+    let mut renamed = second_page["results"][0].clone();
+    renamed["requestedBy"]["displayName"] = serde_json::json!("Robert");
+    requests["results"].as_array_mut().unwrap().push(renamed);
+    requests["pageInfo"]["pages"] = serde_json::json!(1);
+    mocks.respond(
+        "overseerr",
+        "/api/v1/request",
+        200,
+        &serde_json::to_string(&requests).unwrap(),
+    );
+    // Neither request is over the quota on its own, together they are.
+    let env = TestEnv::new(&mocks, "requester_quota: {users: {bob: 30}}");
+
+    let report = report(&env, &[]);
+
+    let requesters = report_section(&report, "Space by requester");
+    let bob = requesters
+        .lines()
+        .find(|line| line.contains("| 2 |"))
+        .unwrap_or_else(|| panic!("Bob's requests are not counted together: {}", requesters));
+    assert!(bob.contains("**Over**"), "{}", requesters);
+}
+
+#[test]
+fn only_looks_up_episodes_for_the_requester_policy() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+
+    report(&env, &[]);
+
+    assert!(mocks
+        .requests_to("sonarr", "GET")
+        .iter()
+        .all(|path| !path.starts_with("/api/v3/episode")));
+}
+
+#[test]
+fn keeps_series_whose_episodes_can_not_be_listed() {
+    let mocks = Mocks::start();
+    mocks.respond("sonarr", "/api/v3/episode", 404, "");
+    let env = TestEnv::new(&mocks, "requester_policy: {min_progress: 90}");
+
+    let report = report(&env, &[]);
+
+    assert!(report.contains("Breaking Bad"), "{}", report);
+    assert!(!report.contains("could not be gathered"), "{}", report);
+}
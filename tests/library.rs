@@ -0,0 +1,66 @@
+mod common;
+
+use common::{Mocks, TestEnv};
+use media_cleaner::{Config, MediaCleaner, StepStatus};
+
+#[tokio::test]
+async fn gathers_and_deletes_with_an_explicit_config() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    let config = std::fs::read_to_string(env.dir.join("config.yaml")).unwrap();
+    let cleaner = MediaCleaner::new(Config::from_yaml(&config).unwrap()).unwrap();
+
+    let (items, errors) = cleaner.gather(false).await.unwrap();
+
+    assert!(errors.is_empty(), "{:?}", errors);
+    let mut titles: Vec<_> = items.iter().map(|item| item.title.as_str()).collect();
+    titles.sort();
+    assert_eq!(titles, ["Breaking Bad", "Heat", "The Matrix"]);
+
+    let heat = items.into_iter().find(|item| item.title == "Heat").unwrap();
+    let result = cleaner.delete(vec![heat]).await.unwrap();
+
+    assert_eq!(result.deleted.len(), 1);
+    assert!(result.outcomes[0]
+        .services
+        .iter()
+        .all(|outcome| matches!(outcome.status, StepStatus::Removed)));
+    assert_eq!(
+        mocks.requests_to("radarr", "DELETE"),
+        ["/api/v3/movie/2?deleteFiles=true&addImportExclusion=false"]
+    );
+    assert!(env.data_file("deleted_items.json").is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn keeps_cleaners_with_different_configs_apart() {
+    let cleaner = |mocks: &Mocks| {
+        let env = TestEnv::new(mocks, "");
+        let config = std::fs::read_to_string(env.dir.join("config.yaml")).unwrap();
+        MediaCleaner::new(Config::from_yaml(&config).unwrap()).unwrap()
+    };
+    let (mocks, other_mocks) = (Mocks::start(), Mocks::start());
+    let (cleaner, other_cleaner) = (cleaner(&mocks), cleaner(&other_mocks));
+
+    let (gathered, other_gathered) =
+        tokio::join!(cleaner.gather(false), other_cleaner.gather(false));
+
+    assert_eq!(gathered.unwrap().0.len(), 3);
+    assert_eq!(other_gathered.unwrap().0.len(), 3);
+    // Each gathering sent all of its requests to its own services.
+    assert_eq!(mocks.requests().len(), other_mocks.requests().len());
+}
+
+#[test]
+fn rejects_an_invalid_config() {
+    let config = "plex: {url: http://plex, token: a}
+overseerr: {url: http://overseerr, api_key: b}
+tautulli: {url: http://tautulli, api_key: c}
+";
+
+    let err = Config::from_yaml(config).unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("You have not configured Sonarr or Radarr"));
+}
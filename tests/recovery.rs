@@ -0,0 +1,201 @@
+mod common;
+
+use common::{successful_stdout, Mocks, TestEnv};
+use portable_pty::{native_pty_system, CommandBuilder, PtySize};
+use serde_json::{json, Value};
+use std::{
+    fs,
+    io::{Read, Write},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+/// What Radarr had of Heat before it was removed.
+fn heat_record() -> Value {
+    json!({
+        "media_type": "movie",
+        "is_4k": false,
+        "title": "Heat",
+        "tmdb_id": 949,
+        "tvdb_id": null,
+        "quality_profile_id": 4,
+        "language_profile_id": null,
+        "root_folder_path": "/data/movies",
+        "monitored": true,
+    })
+}
+
+fn heat_request() -> Value {
+    json!({
+        "media_type": "movie",
+        "tmdb_id": 949,
+        "is_4k": false,
+        "user_id": 2,
+        "requested_by": "bob",
+    })
+}
+
+fn heat_summary() -> Value {
+    json!({
+        "rating_key": "101",
+        "title": "Heat",
+        "media_type": "movie",
+        "size": 12000000000i64,
+    })
+}
+
+fn write_data_file(env: &TestEnv, name: &str, content: &Value) {
+    let dir = env.dir.join("data");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(name), content.to_string()).unwrap();
+}
+
+/// Runs the command in a terminal, typing the keys once the prompt shows up, and returns all
+/// that was printed.
+fn run_in_terminal(env: &TestEnv, args: &[&str], prompt: &str, keys: &str) -> String {
+    let pty = native_pty_system()
+        .openpty(PtySize {
+            rows: 40,
+            cols: 200,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .unwrap();
+    let mut command = CommandBuilder::new(env!("CARGO_BIN_EXE_media-cleaner"));
+    command.args(args);
+    command.cwd(&env.dir);
+    command.env("TERM", "xterm");
+    let mut child = pty.slave.spawn_command(command).unwrap();
+    drop(pty.slave);
+
+    let mut reader = pty.master.try_clone_reader().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0; 1024];
+        while let Ok(read) = reader.read(&mut buffer) {
+            if read == 0 || sender.send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut output = Vec::new();
+    while !String::from_utf8_lossy(&output).contains(prompt) {
+        match receiver.recv_timeout(Duration::from_secs(20)) {
+            Ok(chunk) => output.extend(chunk),
+            Err(_) => panic!("No prompt in: {}", String::from_utf8_lossy(&output)),
+        }
+    }
+    let mut writer = pty.master.take_writer().unwrap();
+    writer.write_all(keys.as_bytes()).unwrap();
+    writer.flush().unwrap();
+
+    let status = child.wait().unwrap();
+    while let Ok(chunk) = receiver.recv_timeout(Duration::from_millis(500)) {
+        output.extend(chunk);
+    }
+    let output = String::from_utf8_lossy(&output).into_owned();
+    assert!(status.success(), "The command failed: {}", output);
+
+    output
+}
+
+#[test]
+fn resumes_an_interrupted_deletion() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    // Heat was removed from Radarr, and the deletion stopped while removing its request.
+    write_data_file(
+        &env,
+        "deletion_plan.json",
+        &json!({
+            "mode": "interactive",
+            "created_at": "2024-06-01T12:00:00Z",
+            "items": [{
+                "item": heat_summary(),
+                "requester": { "user_id": 2, "name": "bob" },
+                "steps": [
+                    {
+                        "action": { "kind": "arr", "id": 2, "record": heat_record() },
+                        "started": true,
+                        "status": { "status": "removed" },
+                    },
+                    {
+                        "action": { "kind": "request", "media_id": 11, "record": heat_request() },
+                        "started": true,
+                        "status": null,
+                    },
+                ],
+            }],
+        }),
+    );
+
+    let output = successful_stdout(&env.run(&["resume"]));
+
+    assert!(output.contains("- Heat from overseerr\n"), "{}", output);
+    assert!(output.contains("1 items were removed"), "{}", output);
+    assert!(mocks.requests_to("radarr", "DELETE").is_empty());
+    let removed = mocks.requests_to("overseerr", "DELETE");
+    assert_eq!(removed.len(), 1, "{:?}", removed);
+    assert!(removed[0].starts_with("/api/v1/media/11"), "{:?}", removed);
+
+    assert!(env.data_file("deletion_plan.json").is_none());
+    let deleted = env.data_file("deleted_items.json").unwrap();
+    assert_eq!(deleted[0]["item"]["title"], "Heat");
+    assert_eq!(deleted[0]["instances"], json!([heat_record()]));
+    assert_eq!(deleted[0]["request"], heat_request());
+}
+
+#[test]
+fn restores_a_deleted_item_with_its_request() {
+    let mocks = Mocks::start();
+    let env = TestEnv::new(&mocks, "");
+    write_data_file(
+        &env,
+        "deleted_items.json",
+        &json!([{
+            "deleted_at": "2024-06-01T12:00:00Z",
+            "item": heat_summary(),
+            "instances": [heat_record()],
+            "request": heat_request(),
+        }]),
+    );
+
+    // Selects the only item and confirms.
+    let output = run_in_terminal(
+        &env,
+        &["restore", "--with-request"],
+        "Choose what media to restore",
+        " \r",
+    );
+
+    assert!(output.contains("Restored Heat."), "{}", output);
+    let added = mocks.requests_to("radarr", "POST");
+    assert_eq!(added.len(), 1, "{:?}", added);
+    assert!(added[0].starts_with("/api/v3/movie "), "{:?}", added);
+    for field in [
+        r#""tmdbId":949"#,
+        r#""qualityProfileId":4"#,
+        r#""rootFolderPath":"/data/movies""#,
+    ] {
+        assert!(
+            added[0].contains(field),
+            "{} is missing: {}",
+            field,
+            added[0]
+        );
+    }
+    let requested = mocks.requests_to("overseerr", "POST");
+    assert_eq!(requested.len(), 1, "{:?}", requested);
+    for field in [r#""mediaId":949"#, r#""userId":2"#] {
+        assert!(
+            requested[0].contains(field),
+            "{} is missing: {}",
+            field,
+            requested[0]
+        );
+    }
+
+    assert_eq!(env.data_file("deleted_items.json").unwrap(), json!([]));
+}